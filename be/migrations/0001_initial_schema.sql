@@ -1,5 +1,9 @@
 -- Cynnycty Database Schema
 -- ArcadeDB Multi-Model Database Schema Definition
+--
+-- Migration 0001: initial Profile schema
+-- Statements use IF NOT EXISTS so databases created before migrations were
+-- tracked can adopt this file without errors.
 
 -- ============================================================================
 -- PROFILE TYPE
@@ -8,32 +12,32 @@
 -- Uses internal userId as primary identifier for all relationships
 -- clerkId is just for auth provider mapping (can be swapped later)
 
-CREATE DOCUMENT TYPE Profile;
+CREATE DOCUMENT TYPE Profile IF NOT EXISTS;
 
 -- Core Identity Fields
 -- userId: Our internal immutable identifier (UUID) - PRIMARY KEY
 -- clerkId: Clerk's external auth ID (nullable for auth provider flexibility)
-CREATE PROPERTY Profile.userId STRING;
-CREATE PROPERTY Profile.clerkId STRING;
+CREATE PROPERTY Profile.userId IF NOT EXISTS STRING;
+CREATE PROPERTY Profile.clerkId IF NOT EXISTS STRING;
 
 -- Profile Data Fields
-CREATE PROPERTY Profile.displayName STRING;
-CREATE PROPERTY Profile.aboutMe STRING;
-CREATE PROPERTY Profile.avatarUrl STRING;
+CREATE PROPERTY Profile.displayName IF NOT EXISTS STRING;
+CREATE PROPERTY Profile.aboutMe IF NOT EXISTS STRING;
+CREATE PROPERTY Profile.avatarUrl IF NOT EXISTS STRING;
 
 -- Timestamps
-CREATE PROPERTY Profile.createdAt DATETIME;
-CREATE PROPERTY Profile.updatedAt DATETIME;
+CREATE PROPERTY Profile.createdAt IF NOT EXISTS DATETIME;
+CREATE PROPERTY Profile.updatedAt IF NOT EXISTS DATETIME;
 
 -- ============================================================================
 -- INDEXES
 -- ============================================================================
 -- Unique index on userId - our source of truth for all operations
-CREATE INDEX Profile_userId_idx ON Profile (userId) UNIQUE;
+CREATE INDEX Profile_userId_idx IF NOT EXISTS ON Profile (userId) UNIQUE;
 
 -- Unique index on clerkId - for fast auth provider lookups
 -- This is how we map from Clerk's ID to our internal userId
-CREATE INDEX Profile_clerkId_idx ON Profile (clerkId) UNIQUE;
+CREATE INDEX Profile_clerkId_idx IF NOT EXISTS ON Profile (clerkId) UNIQUE;
 
 -- ============================================================================
 -- FUTURE: Social Graph Edge Types (Commented for now)
@@ -0,0 +1,7 @@
+-- Migration 0002: soft deletion of profiles
+--
+-- deletedAt is set when the auth provider reports the user deleted (Clerk
+-- `user.deleted` webhook). The row stays as a tombstone so userId references
+-- remain valid; personal fields are cleared.
+
+CREATE PROPERTY Profile.deletedAt IF NOT EXISTS DATETIME;
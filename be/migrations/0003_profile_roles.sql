@@ -0,0 +1,6 @@
+-- Migration 0003: authorization roles on Profile
+--
+-- roles holds lowercase role names ("moderator", "admin"). Missing or null
+-- means an ordinary user.
+
+CREATE PROPERTY Profile.roles IF NOT EXISTS LIST OF STRING;
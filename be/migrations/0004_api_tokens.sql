@@ -0,0 +1,22 @@
+-- Migration 0004: personal access tokens
+--
+-- Only the SHA-256 hash of a token is stored; the plaintext (cyn_...) is
+-- shown once when created. prefix keeps the first characters so users can
+-- tell tokens apart. Revoked tokens are kept with revokedAt set.
+
+CREATE DOCUMENT TYPE ApiToken IF NOT EXISTS;
+
+CREATE PROPERTY ApiToken.tokenId IF NOT EXISTS STRING;
+CREATE PROPERTY ApiToken.userId IF NOT EXISTS STRING;   -- References Profile.userId
+CREATE PROPERTY ApiToken.name IF NOT EXISTS STRING;
+CREATE PROPERTY ApiToken.tokenHash IF NOT EXISTS STRING;
+CREATE PROPERTY ApiToken.prefix IF NOT EXISTS STRING;
+CREATE PROPERTY ApiToken.scopes IF NOT EXISTS LIST OF STRING;
+CREATE PROPERTY ApiToken.createdAt IF NOT EXISTS DATETIME;
+CREATE PROPERTY ApiToken.expiresAt IF NOT EXISTS DATETIME;
+CREATE PROPERTY ApiToken.lastUsedAt IF NOT EXISTS DATETIME;
+CREATE PROPERTY ApiToken.revokedAt IF NOT EXISTS DATETIME;
+
+CREATE INDEX ApiToken_tokenId_idx IF NOT EXISTS ON ApiToken (tokenId) UNIQUE;
+CREATE INDEX ApiToken_tokenHash_idx IF NOT EXISTS ON ApiToken (tokenHash) UNIQUE;
+CREATE INDEX ApiToken_userId_idx IF NOT EXISTS ON ApiToken (userId) NOTUNIQUE;
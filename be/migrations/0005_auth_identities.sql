@@ -0,0 +1,21 @@
+-- Migration 0005: external identities linked to profiles
+--
+-- Each row maps an auth provider's subject (Clerk user ID, OIDC `sub`, dev
+-- subject) to our userId, so one profile can be reached from several
+-- providers and survive a provider migration. provider is the name the auth
+-- provider reports, e.g. "clerk".
+--
+-- Profile.clerkId is kept for existing profiles only: on first sign-in the
+-- backend finds them by clerkId and records the identity here. New profiles
+-- leave it null.
+
+CREATE DOCUMENT TYPE AuthIdentity IF NOT EXISTS;
+
+CREATE PROPERTY AuthIdentity.provider IF NOT EXISTS STRING;
+CREATE PROPERTY AuthIdentity.subject IF NOT EXISTS STRING;
+CREATE PROPERTY AuthIdentity.userId IF NOT EXISTS STRING;   -- References Profile.userId
+CREATE PROPERTY AuthIdentity.linkedAt IF NOT EXISTS DATETIME;
+
+-- A subject of a provider belongs to exactly one profile
+CREATE INDEX AuthIdentity_provider_subject_idx IF NOT EXISTS ON AuthIdentity (provider, subject) UNIQUE;
+CREATE INDEX AuthIdentity_userId_idx IF NOT EXISTS ON AuthIdentity (userId) NOTUNIQUE;
@@ -0,0 +1,27 @@
+-- Migration 0006: account deletion and erasure records
+--
+-- Deleting an account first schedules it: deletionScheduledFor is set to the
+-- end of the grace period, during which the user can cancel. A background
+-- job then erases the profile and everything keyed by its userId, and writes
+-- an ErasureRecord.
+--
+-- ErasureRecord is kept for compliance and holds no personal data: only the
+-- userId and SHA-256 hashes of the erased identities (provider + subject),
+-- which also stop those identities from being provisioned a fresh profile.
+
+CREATE PROPERTY Profile.deletionRequestedAt IF NOT EXISTS DATETIME;
+CREATE PROPERTY Profile.deletionScheduledFor IF NOT EXISTS DATETIME;
+CREATE PROPERTY Profile.deletionRequestedBy IF NOT EXISTS STRING;   -- "self", "admin:<userId>" or "clerk"
+
+CREATE INDEX Profile_deletionScheduledFor_idx IF NOT EXISTS ON Profile (deletionScheduledFor) NOTUNIQUE;
+
+CREATE DOCUMENT TYPE ErasureRecord IF NOT EXISTS;
+
+CREATE PROPERTY ErasureRecord.userId IF NOT EXISTS STRING;
+CREATE PROPERTY ErasureRecord.subjectHashes IF NOT EXISTS LIST OF STRING;
+CREATE PROPERTY ErasureRecord.requestedBy IF NOT EXISTS STRING;
+CREATE PROPERTY ErasureRecord.requestedAt IF NOT EXISTS DATETIME;
+CREATE PROPERTY ErasureRecord.erasedAt IF NOT EXISTS DATETIME;
+
+-- An account is erased once; a retried erasure finds the existing record
+CREATE INDEX ErasureRecord_userId_idx IF NOT EXISTS ON ErasureRecord (userId) UNIQUE;
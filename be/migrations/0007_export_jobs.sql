@@ -0,0 +1,21 @@
+-- Migration 0007: personal data exports
+--
+-- An export job collects everything stored about a user into a JSON archive
+-- (see fixtures/export-v1.json for the format). status moves from "pending"
+-- to "running" to "ready" or "failed". The archive is kept on the job until
+-- expiresAt, after which it is deleted with the job.
+
+CREATE DOCUMENT TYPE ExportJob IF NOT EXISTS;
+
+CREATE PROPERTY ExportJob.exportId IF NOT EXISTS STRING;
+CREATE PROPERTY ExportJob.userId IF NOT EXISTS STRING;   -- References Profile.userId
+CREATE PROPERTY ExportJob.status IF NOT EXISTS STRING;
+CREATE PROPERTY ExportJob.requestedAt IF NOT EXISTS DATETIME;
+CREATE PROPERTY ExportJob.completedAt IF NOT EXISTS DATETIME;
+CREATE PROPERTY ExportJob.expiresAt IF NOT EXISTS DATETIME;
+CREATE PROPERTY ExportJob.error IF NOT EXISTS STRING;
+CREATE PROPERTY ExportJob.archive IF NOT EXISTS STRING;
+
+CREATE INDEX ExportJob_exportId_idx IF NOT EXISTS ON ExportJob (exportId) UNIQUE;
+CREATE INDEX ExportJob_userId_idx IF NOT EXISTS ON ExportJob (userId) NOTUNIQUE;
+CREATE INDEX ExportJob_expiresAt_idx IF NOT EXISTS ON ExportJob (expiresAt) NOTUNIQUE;
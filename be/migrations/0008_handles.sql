@@ -0,0 +1,28 @@
+-- Migration 0008: user handles and handle history
+--
+-- handle is the URL-safe name used for mentions and profile URLs, stored as
+-- the user typed it; handleLower is its lowercase form and carries the
+-- unique index, so "Ada" and "ada" can't both be taken. Profiles without a
+-- handle leave both null.
+--
+-- When a user changes their handle the old one is written to HandleHistory.
+-- Until expiresAt it redirects to the profile and only that user can take it
+-- back; afterwards it is free for anyone.
+
+CREATE PROPERTY Profile.handle IF NOT EXISTS STRING;
+CREATE PROPERTY Profile.handleLower IF NOT EXISTS STRING;
+CREATE PROPERTY Profile.handleChangedAt IF NOT EXISTS DATETIME;
+
+CREATE INDEX Profile_handleLower_idx IF NOT EXISTS ON Profile (handleLower) UNIQUE;
+
+CREATE DOCUMENT TYPE HandleHistory IF NOT EXISTS;
+
+CREATE PROPERTY HandleHistory.handle IF NOT EXISTS STRING;
+CREATE PROPERTY HandleHistory.handleLower IF NOT EXISTS STRING;
+CREATE PROPERTY HandleHistory.userId IF NOT EXISTS STRING;   -- References Profile.userId
+CREATE PROPERTY HandleHistory.releasedAt IF NOT EXISTS DATETIME;
+CREATE PROPERTY HandleHistory.expiresAt IF NOT EXISTS DATETIME;
+
+-- A handle can be released more than once, by the same or different users
+CREATE INDEX HandleHistory_handleLower_idx IF NOT EXISTS ON HandleHistory (handleLower) NOTUNIQUE;
+CREATE INDEX HandleHistory_userId_idx IF NOT EXISTS ON HandleHistory (userId) NOTUNIQUE;
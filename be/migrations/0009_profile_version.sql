@@ -0,0 +1,11 @@
+-- Migration 0009: profile version for optimistic concurrency
+--
+-- version starts at 1 and every write to the profile increments it. It is
+-- served as the profile's ETag; an edit sent with If-Match (or a version in
+-- the body) only applies while the profile is still at that version, so two
+-- clients editing at once can't silently overwrite each other.
+
+CREATE PROPERTY Profile.version IF NOT EXISTS LONG;
+
+-- Profiles created before this migration start at version 1
+UPDATE Profile SET version = 1 WHERE version IS NULL;
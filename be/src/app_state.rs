@@ -1,17 +1,61 @@
+use axum::extract::FromRef;
 use std::sync::Arc;
 
-use crate::auth::clerk::ClerkJwks;
+use crate::auth::AuthProvider;
+use crate::config::AppConfig;
 use crate::db::connection::DatabaseConnection;
+use crate::dependencies::Dependencies;
+use crate::repository::{
+    ApiTokenRepository, AuthIdentityRepository, ErasureRepository, ExportRepository, HandleHistoryRepository,
+    ProfileRepository, Repositories,
+};
 
 /// Shared application state
 #[derive(Clone)]
 pub struct AppState {
+    pub config: Arc<AppConfig>,
     pub db: DatabaseConnection,
-    pub clerk_jwks: Arc<ClerkJwks>,
+    pub auth: Arc<dyn AuthProvider>,
+    pub profiles: Arc<dyn ProfileRepository>,
+    pub identities: Arc<dyn AuthIdentityRepository>,
+    pub api_tokens: Arc<dyn ApiTokenRepository>,
+    pub erasures: Arc<dyn ErasureRepository>,
+    pub exports: Arc<dyn ExportRepository>,
+    pub handle_history: Arc<dyn HandleHistoryRepository>,
+    pub dependencies: Arc<Dependencies>,
 }
 
 impl AppState {
-    pub fn new(db: DatabaseConnection, clerk_jwks: Arc<ClerkJwks>) -> Self {
-        Self { db, clerk_jwks }
+    pub fn new(
+        config: Arc<AppConfig>,
+        db: DatabaseConnection,
+        auth: Arc<dyn AuthProvider>,
+        repositories: Repositories,
+        dependencies: Arc<Dependencies>,
+    ) -> Self {
+        Self {
+            config,
+            db,
+            auth,
+            profiles: repositories.profiles,
+            identities: repositories.identities,
+            api_tokens: repositories.api_tokens,
+            erasures: repositories.erasures,
+            exports: repositories.exports,
+            handle_history: repositories.handle_history,
+            dependencies,
+        }
+    }
+}
+
+impl FromRef<AppState> for DatabaseConnection {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Dependencies> {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.dependencies.clone()
     }
 }
@@ -0,0 +1,106 @@
+//! Personal access tokens
+//!
+//! A token is `cyn_` followed by 64 hex characters from two random UUIDs.
+//! Only its SHA-256 hash is stored, so a database leak does not leak usable
+//! credentials; the high entropy makes an unsalted hash sufficient and lets
+//! the hash itself be the lookup key.
+
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+
+use crate::auth::error::AuthError;
+use crate::auth::AuthUser;
+use crate::db::error::DbError;
+use crate::repository::{ApiTokenRepository, ProfileRepository};
+
+/// Marks a bearer credential as an API token rather than a JWT
+pub const TOKEN_PREFIX: &str = "cyn_";
+
+/// [`AuthUser::provider`] of requests authenticated with an API token
+pub const API_TOKEN_PROVIDER: &str = "api_token";
+
+/// Characters of the plaintext kept for display, e.g. `cyn_1a2b3c4d`
+const DISPLAY_PREFIX_LEN: usize = TOKEN_PREFIX.len() + 8;
+
+/// A freshly generated token; `plaintext` is shown to the user once
+pub struct GeneratedToken {
+    pub plaintext: String,
+    pub hash: String,
+    pub prefix: String,
+}
+
+pub fn generate() -> GeneratedToken {
+    let plaintext = format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+
+    GeneratedToken {
+        hash: hash(&plaintext),
+        prefix: plaintext[..DISPLAY_PREFIX_LEN].to_string(),
+        plaintext,
+    }
+}
+
+/// Hex SHA-256 of a token's plaintext
+pub fn hash(plaintext: &str) -> String {
+    Sha256::digest(plaintext.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn is_api_token(bearer: &str) -> bool {
+    bearer.starts_with(TOKEN_PREFIX)
+}
+
+/// Why an API token was not accepted
+pub enum ApiTokenError {
+    Invalid(AuthError),
+    /// The owner's profile was tombstoned
+    ProfileDeleted,
+    Db(DbError),
+}
+
+impl From<DbError> for ApiTokenError {
+    fn from(e: DbError) -> Self {
+        Self::Db(e)
+    }
+}
+
+/// Resolve an API token to its owner, restricted to the token's scopes
+pub async fn authenticate(
+    tokens: &dyn ApiTokenRepository,
+    profiles: &dyn ProfileRepository,
+    plaintext: &str,
+) -> Result<AuthUser, ApiTokenError> {
+    let token = tokens
+        .find_by_hash(&hash(plaintext))
+        .await?
+        .ok_or(ApiTokenError::Invalid(AuthError::InvalidApiToken))?;
+
+    if !token.is_active(Utc::now()) {
+        return Err(ApiTokenError::Invalid(AuthError::InvalidApiToken));
+    }
+
+    let profile = profiles
+        .find_by_user_id(&token.user_id)
+        .await?
+        .ok_or(ApiTokenError::Invalid(AuthError::InvalidApiToken))?;
+    if profile.is_deleted() {
+        return Err(ApiTokenError::ProfileDeleted);
+    }
+
+    // Usage tracking is best effort; never fail the request over it
+    if let Err(e) = tokens.touch(&token.token_id).await {
+        tracing::warn!("Failed to record use of API token {}: {}", token.token_id, e);
+    }
+
+    tracing::debug!("Authenticated API token {} of user {}", token.token_id, token.user_id);
+
+    Ok(AuthUser::new(API_TOKEN_PROVIDER.to_string(), token.token_id, profile.user_id)
+        .with_display_name(profile.display_name)
+        .with_scopes(token.scopes))
+}
@@ -1,15 +1,32 @@
-use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use async_trait::async_trait;
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::error::AuthError;
+use crate::auth::jwks::{JwksCache, JwksMetrics};
+use crate::auth::provider::{AuthProvider, Identity};
+use crate::auth::roles::{parse_roles, Role};
+use crate::config::DEFAULT_LEEWAY_SECONDS;
+use crate::dependencies::Dependencies;
 use base64::{Engine as _, engine::general_purpose};
 
+/// Provider name recorded on Clerk identities
+pub const PROVIDER_NAME: &str = "clerk";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClerkClaims {
     pub sub: String, // This is the clerkId
     pub exp: usize,
     pub iat: usize,
+    #[serde(default)]
+    pub nbf: Option<usize>,
     pub iss: String,
-    pub azp: String,
+    /// Origin of the frontend that requested the token; absent on tokens
+    /// not issued to a browser session
+    #[serde(default)]
+    pub azp: Option<String>,
     #[serde(default)]
     pub email: Option<String>,
     #[serde(default)]
@@ -20,110 +37,300 @@ pub struct ClerkClaims {
     pub given_name: Option<String>,
     #[serde(default)]
     pub family_name: Option<String>,
+    /// The user's public metadata, if the session token template adds
+    /// `"metadata": "{{user.public_metadata}}"`
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
-struct JwksResponse {
-    keys: Vec<JwkKey>,
+impl ClerkClaims {
+    /// Roles from `metadata.roles` (a list) or `metadata.role` (a string)
+    pub fn metadata_roles(&self) -> Vec<Role> {
+        let Some(metadata) = &self.metadata else {
+            return Vec::new();
+        };
+
+        match (metadata.get("roles"), metadata.get("role")) {
+            (Some(serde_json::Value::Array(names)), _) => {
+                parse_roles(names.iter().filter_map(serde_json::Value::as_str))
+            }
+            (_, Some(serde_json::Value::String(name))) => parse_roles([name.as_str()]),
+            _ => Vec::new(),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct JwkKey {
-    #[serde(rename = "kid")]
-    key_id: String,
-    #[serde(rename = "n")]
-    modulus: String,
-    #[serde(rename = "e")]
-    exponent: String,
+/// Decode the Clerk frontend API domain embedded in a publishable key
+pub fn frontend_api_domain(publishable_key: &str) -> Result<String, String> {
+    // Format: pk_test_<base64>  or pk_live_<base64>
+    // The base64 part encodes the Clerk frontend API domain
+    let parts: Vec<&str> = publishable_key.split('_').collect();
+    if parts.len() < 3 || parts[0] != "pk" {
+        return Err("Invalid Clerk publishable key format".to_string());
+    }
+
+    // Decode the base64 to get the domain
+    // Note: Clerk's publishable key format is pk_test_<base64>$
+    // The $ at the end is not base64, it's a delimiter
+    // The decoded value also has $ at the end which needs stripping
+    let encoded = parts[2].trim_end_matches('$');
+
+    let decoded = general_purpose::STANDARD_NO_PAD
+        .decode(encoded)
+        .map_err(|e| format!("Failed to decode publishable key: {}", e))?;
+    let decoded = String::from_utf8(decoded)
+        .map_err(|_| "Publishable key does not encode a domain".to_string())?;
+
+    // The decoded string also ends with $, strip it
+    Ok(decoded.trim_end_matches('$').to_string())
 }
 
+/// Clerk's signing keys, cached and refreshed by a [`JwksCache`]
+///
+/// Starts empty so the server can come up while Clerk is unreachable;
+/// verification fails until the first successful refresh.
 pub struct ClerkJwks {
-    keys: HashMap<String, DecodingKey>,
-    publishable_key: String,
+    cache: Arc<JwksCache>,
+    allowed_issuers: Vec<String>,
+    /// Accepted `azp` values; empty accepts any
+    authorized_parties: Vec<String>,
+    leeway: Duration,
+    roles_from_metadata: bool,
 }
 
 impl ClerkJwks {
-    pub async fn new(publishable_key: String) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut jwks = Self {
-            keys: HashMap::new(),
-            publishable_key: publishable_key.clone(),
-        };
-        jwks.fetch_jwks().await?;
-        Ok(jwks)
+    /// Trust the instance encoded in the publishable key: its JWKS, and its
+    /// frontend API as the only issuer
+    pub fn new(publishable_key: &str) -> Result<Self, String> {
+        let domain = frontend_api_domain(publishable_key)?;
+        let jwks_url = format!("https://{}/.well-known/jwks.json", domain);
+
+        Ok(Self {
+            cache: Arc::new(JwksCache::new(jwks_url)),
+            allowed_issuers: vec![format!("https://{}", domain)],
+            authorized_parties: Vec::new(),
+            leeway: Duration::from_secs(DEFAULT_LEEWAY_SECONDS),
+            roles_from_metadata: false,
+        })
+    }
+
+    /// Replace the issuer derived from the publishable key, e.g. for a
+    /// custom Clerk domain
+    pub fn with_allowed_issuers(mut self, issuers: Vec<String>) -> Self {
+        self.allowed_issuers = issuers;
+        self
     }
 
-    async fn fetch_jwks(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // Extract the instance ID from the publishable key
-        // Format: pk_test_<base64>  or pk_live_<base64>
-        // The base64 part encodes the Clerk frontend API domain
-        let parts: Vec<&str> = self.publishable_key.split('_').collect();
-        if parts.len() < 3 {
-            return Err("Invalid Clerk publishable key format".into());
+    pub fn with_authorized_parties(mut self, parties: Vec<String>) -> Self {
+        self.authorized_parties = parties;
+        self
+    }
+
+    /// Clock skew tolerated on `exp` and `nbf`
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Trust roles in the token's public metadata claim
+    pub fn with_roles_from_metadata(mut self, enabled: bool) -> Self {
+        self.roles_from_metadata = enabled;
+        self
+    }
+
+    pub fn cache(&self) -> &Arc<JwksCache> {
+        &self.cache
+    }
+
+    pub async fn verify_token(&self, token: &str) -> Result<ClerkClaims, AuthError> {
+        // Decode the header to get the key ID
+        let header = decode_header(token)?;
+        let kid = header
+            .kid
+            .ok_or_else(|| AuthError::Malformed("missing 'kid' in header".to_string()))?;
+
+        // Get the corresponding decoding key, refetching the set if the
+        // key ID is new (Clerk rotated its keys)
+        let decoding_key = self.cache.key(&kid).await?;
+
+        // Clerk session tokens carry no standard `aud`; the authorized
+        // party (`azp`) plays that role and is checked below
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.validate_aud = false;
+        validation.validate_exp = true;
+        validation.validate_nbf = true;
+        validation.leeway = self.leeway.as_secs();
+
+        // Decode and verify the token
+        let claims = decode::<ClerkClaims>(token, &decoding_key, &validation)?.claims;
+
+        if !matches_any(&self.allowed_issuers, &claims.iss) {
+            return Err(AuthError::WrongIssuer(claims.iss));
         }
 
-        // Decode the base64 to get the domain
-        // Note: Clerk's publishable key format is pk_test_<base64>$
-        // The $ at the end is not base64, it's a delimiter
-        // The decoded value also has $ at the end which needs stripping
-        let encoded = parts[2].trim_end_matches('$');
+        if let Some(azp) = &claims.azp
+            && !self.authorized_parties.is_empty()
+            && !matches_any(&self.authorized_parties, azp)
+        {
+            return Err(AuthError::WrongAuthorizedParty(azp.clone()));
+        }
 
-        let decoded = String::from_utf8(
-            general_purpose::STANDARD_NO_PAD.decode(encoded)
-                .map_err(|e| format!("Failed to decode publishable key: {}", e))?,
-        )?;
+        Ok(claims)
+    }
+}
 
-        // The decoded string also ends with $, strip it
-        let domain = decoded.trim_end_matches('$');
+/// Whether `value` is one of the `allowed` URLs, ignoring a trailing slash
+pub(crate) fn matches_any(allowed: &[String], value: &str) -> bool {
+    allowed
+        .iter()
+        .any(|candidate| candidate.trim_end_matches('/') == value.trim_end_matches('/'))
+}
 
-        let jwks_url = format!("https://{}/.well-known/jwks.json", domain);
+#[async_trait]
+impl AuthProvider for ClerkJwks {
+    fn name(&self) -> &str {
+        PROVIDER_NAME
+    }
+
+    async fn verify(&self, token: &str) -> Result<Identity, AuthError> {
+        let claims = self.verify_token(token).await?;
+        let roles = if self.roles_from_metadata {
+            claims.metadata_roles()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Identity {
+            subject: claims.sub,
+            provider: self.name().to_string(),
+            email: claims.email,
+            email_verified: claims.email_verified.unwrap_or(false),
+            name: claims.name,
+            roles,
+        })
+    }
+
+    fn start(&self, dependencies: Arc<Dependencies>) {
+        self.cache.clone().spawn_refresh(dependencies);
+    }
+
+    fn key_metrics(&self) -> Option<JwksMetrics> {
+        Some(self.cache.metrics())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Json, Router};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::{json, Value};
 
-        tracing::info!("Fetching JWKS from: {}", jwks_url);
+    const ISSUER: &str = "https://clerk.example.com";
+    const APP: &str = "https://app.example.com";
 
-        let response = reqwest::get(&jwks_url).await?;
-        let jwks_response: JwksResponse = response.json().await?;
+    /// A key set holding `fixtures/test-rsa-key.pem` as `test-key`, served
+    /// locally
+    async fn jwks() -> ClerkJwks {
+        let keys: Value = serde_json::from_str(include_str!("../../fixtures/test-jwks.json")).unwrap();
+        let app = Router::new().route("/.well-known/jwks.json", get(move || async move { Json(keys) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.ok() });
 
-        for key in jwks_response.keys {
-            let decoding_key = DecodingKey::from_rsa_components(&key.modulus, &key.exponent)?;
-            self.keys.insert(key.key_id, decoding_key);
+        let cache = Arc::new(JwksCache::new(format!("http://{}/.well-known/jwks.json", addr)));
+        cache.refresh().await.unwrap();
+
+        ClerkJwks {
+            cache,
+            allowed_issuers: vec![ISSUER.to_string()],
+            authorized_parties: vec![APP.to_string()],
+            leeway: Duration::from_secs(DEFAULT_LEEWAY_SECONDS),
+            roles_from_metadata: false,
         }
+    }
 
-        tracing::info!("Loaded {} JWKS keys", self.keys.len());
-        Ok(())
+    fn now() -> i64 {
+        chrono::Utc::now().timestamp()
     }
 
-    pub async fn verify_token(
-        &self,
-        token: &str,
-    ) -> Result<ClerkClaims, Box<dyn std::error::Error>> {
-        // Decode the header to get the key ID
-        let header = decode_header(token).map_err(|e| {
-            tracing::error!("Failed to decode JWT header: {}", e);
-            e
-        })?;
-
-        let kid = header.kid.ok_or("Token missing 'kid' in header")?;
-
-        // Get the corresponding decoding key
-        let decoding_key = self
-            .keys
-            .get(&kid)
-            .ok_or_else(|| {
-                tracing::error!("Unknown key ID '{}' in token header. Available keys: {:?}", kid, self.keys.keys().collect::<Vec<_>>());
-                "Unknown key ID in token header"
-            })?;
-
-        // Set up validation
-        // Clerk tokens don't use audience validation in the standard way
-        let mut validation = Validation::new(Algorithm::RS256);
-        validation.validate_aud = false; // Clerk doesn't use standard aud claim
-        validation.validate_exp = true;
+    /// Valid claims for `user_1`; `changes` overrides or removes (with
+    /// `null`) individual claims
+    fn token(kid: &str, changes: Value) -> String {
+        let mut claims = json!({
+            "sub": "user_1",
+            "iat": now(),
+            "exp": now() + 300,
+            "iss": ISSUER,
+            "azp": APP,
+        });
+        for (claim, value) in changes.as_object().unwrap() {
+            if value.is_null() {
+                claims.as_object_mut().unwrap().remove(claim);
+            } else {
+                claims[claim] = value.clone();
+            }
+        }
 
-        // Decode and verify the token
-        let token_data = decode::<ClerkClaims>(token, decoding_key, &validation).map_err(|e| {
-            tracing::error!("JWT decode failed: {}", e);
-            e
-        })?;
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.to_string());
+        let key = EncodingKey::from_rsa_pem(include_bytes!("../../fixtures/test-rsa-key.pem")).unwrap();
+        encode(&header, &claims, &key).unwrap()
+    }
+
+    #[tokio::test]
+    async fn valid_token_is_accepted() {
+        let claims = jwks().await.verify_token(&token("test-key", json!({}))).await.unwrap();
+        assert_eq!(claims.sub, "user_1");
+    }
+
+    #[tokio::test]
+    async fn wrong_issuer_is_rejected() {
+        let error = jwks()
+            .await
+            .verify_token(&token("test-key", json!({ "iss": "https://evil.example.com" })))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AuthError::WrongIssuer(_)), "{:?}", error);
+        assert_eq!(error.code(), "wrong_issuer");
+    }
+
+    #[tokio::test]
+    async fn authorized_party_must_be_listed_when_present() {
+        let jwks = jwks().await;
+
+        let error = jwks
+            .verify_token(&token("test-key", json!({ "azp": "https://evil.example.com" })))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AuthError::WrongAuthorizedParty(_)), "{:?}", error);
+        assert_eq!(error.code(), "wrong_authorized_party");
+
+        // Tokens not issued to a browser session carry no `azp`
+        assert!(jwks.verify_token(&token("test-key", json!({ "azp": null }))).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn not_before_allows_the_leeway() {
+        let jwks = jwks().await;
+        let leeway = DEFAULT_LEEWAY_SECONDS as i64;
+
+        let error = jwks
+            .verify_token(&token("test-key", json!({ "nbf": now() + leeway + 60 })))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AuthError::NotYetValid), "{:?}", error);
+
+        let within = token("test-key", json!({ "nbf": now() + leeway / 2 }));
+        assert!(jwks.verify_token(&within).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn unknown_kid_is_rejected_after_a_refetch() {
+        let jwks = jwks().await;
 
-        Ok(token_data.claims)
+        let error = jwks.verify_token(&token("rotated-away", json!({}))).await.unwrap_err();
+        assert!(matches!(&error, AuthError::UnknownKid(kid) if kid == "rotated-away"), "{:?}", error);
+        assert_eq!(jwks.cache.metrics().unknown_kid_refetches, 1);
     }
 }
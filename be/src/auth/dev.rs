@@ -0,0 +1,120 @@
+//! Local development auth with self-signed HS256 tokens
+//!
+//! Only active with `AUTH_PROVIDER=dev`. Tokens are signed with the
+//! configured secret and minted by `cynnycty-backend mint-token` or
+//! `POST /api/v1/dev/tokens`, so the frontend and integration tests can
+//! authenticate without reaching Clerk.
+
+use async_trait::async_trait;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::auth::error::AuthError;
+use crate::auth::provider::{AuthProvider, Identity};
+use crate::config::DEFAULT_LEEWAY_SECONDS;
+use crate::dependencies::Dependencies;
+
+/// `iss` claim of every dev token
+pub const DEV_ISSUER: &str = "cynnycty-dev";
+
+/// Shortest secret accepted for signing dev tokens
+pub const MIN_SECRET_LEN: usize = 32;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DevClaims {
+    pub sub: String,
+    pub iss: String,
+    pub iat: u64,
+    pub exp: u64,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+pub struct DevAuth {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    leeway: Duration,
+}
+
+impl DevAuth {
+    pub fn new(secret: &str) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            leeway: Duration::from_secs(DEFAULT_LEEWAY_SECONDS),
+        }
+    }
+
+    /// Clock skew tolerated on `exp`
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Sign a token for `subject`, valid for `ttl`; returns the token and
+    /// its expiry as a Unix timestamp
+    pub fn mint(
+        &self,
+        subject: &str,
+        email: Option<String>,
+        name: Option<String>,
+        ttl: Duration,
+    ) -> Result<(String, u64), jsonwebtoken::errors::Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let claims = DevClaims {
+            sub: subject.to_string(),
+            iss: DEV_ISSUER.to_string(),
+            iat: now,
+            exp: now + ttl.as_secs(),
+            email_verified: email.is_some(),
+            email,
+            name,
+        };
+
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)?;
+        Ok((token, claims.exp))
+    }
+}
+
+#[async_trait]
+impl AuthProvider for DevAuth {
+    fn name(&self) -> &str {
+        "dev"
+    }
+
+    async fn verify(&self, token: &str) -> Result<Identity, AuthError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.validate_aud = false;
+        validation.leeway = self.leeway.as_secs();
+
+        let claims = decode::<DevClaims>(token, &self.decoding_key, &validation)?.claims;
+
+        if claims.iss != DEV_ISSUER {
+            return Err(AuthError::WrongIssuer(claims.iss));
+        }
+
+        Ok(Identity {
+            subject: claims.sub,
+            provider: self.name().to_string(),
+            email: claims.email,
+            email_verified: claims.email_verified,
+            name: claims.name,
+            roles: Vec::new(),
+        })
+    }
+
+    fn start(&self, dependencies: Arc<Dependencies>) {
+        // Nothing to fetch: the signing secret is local
+        dependencies.auth.mark_up();
+    }
+}
@@ -0,0 +1,120 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use jsonwebtoken::errors::ErrorKind;
+
+use crate::auth::jwks::JwksError;
+
+/// Why a request's credentials were rejected
+///
+/// Issuer and authorized-party checks are done by the providers rather than
+/// `jsonwebtoken` so the rejected value can be reported. Every variant
+/// answers 401 with a JSON body whose `code` tells clients whether
+/// refreshing the session token is worth trying.
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("missing bearer token")]
+    MissingToken,
+    #[error("malformed token: {0}")]
+    Malformed(String),
+    #[error("token has expired")]
+    Expired,
+    #[error("token is not valid yet")]
+    NotYetValid,
+    #[error("token signature is invalid")]
+    BadSignature,
+    #[error("token was signed with unknown key ID '{0}'")]
+    UnknownKid(String),
+    #[error("token issuer '{0}' is not allowed")]
+    WrongIssuer(String),
+    #[error("token authorized party '{0}' is not allowed")]
+    WrongAuthorizedParty(String),
+    #[error("token audience is not allowed")]
+    WrongAudience,
+    #[error("signing keys are unavailable: {0}")]
+    KeysUnavailable(String),
+    #[error("API token is unknown, expired or revoked")]
+    InvalidApiToken,
+}
+
+impl AuthError {
+    /// Stable machine-readable code for the response body
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingToken => "missing_token",
+            AuthError::Malformed(_) => "malformed_token",
+            AuthError::Expired => "token_expired",
+            AuthError::NotYetValid => "token_not_yet_valid",
+            AuthError::BadSignature => "bad_signature",
+            AuthError::UnknownKid(_) => "unknown_kid",
+            AuthError::WrongIssuer(_) => "wrong_issuer",
+            AuthError::WrongAuthorizedParty(_) => "wrong_authorized_party",
+            AuthError::WrongAudience => "wrong_audience",
+            AuthError::KeysUnavailable(_) => "keys_unavailable",
+            AuthError::InvalidApiToken => "invalid_api_token",
+        }
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AuthError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        match e.kind() {
+            ErrorKind::ExpiredSignature => AuthError::Expired,
+            ErrorKind::ImmatureSignature => AuthError::NotYetValid,
+            ErrorKind::InvalidSignature => AuthError::BadSignature,
+            ErrorKind::InvalidAudience => AuthError::WrongAudience,
+            ErrorKind::MissingRequiredClaim(claim) if claim == "aud" => AuthError::WrongAudience,
+            _ => AuthError::Malformed(e.to_string()),
+        }
+    }
+}
+
+impl From<JwksError> for AuthError {
+    fn from(e: JwksError) -> Self {
+        match e {
+            JwksError::UnknownKid(kid) => AuthError::UnknownKid(kid),
+            other => AuthError::KeysUnavailable(other.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "error": "unauthorized",
+            "code": self.code(),
+            "message": self.to_string(),
+        });
+
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, format!("Bearer error=\"invalid_token\", error_description=\"{}\"", self.code()))],
+            Json(body),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rejection_body_and_challenge_carry_the_code() {
+        let response = AuthError::WrongIssuer("https://evil.example.com".to_string()).into_response();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            "Bearer error=\"invalid_token\", error_description=\"wrong_issuer\""
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "unauthorized");
+        assert_eq!(body["code"], "wrong_issuer");
+        assert_eq!(body["message"], "token issuer 'https://evil.example.com' is not allowed");
+    }
+}
@@ -0,0 +1,380 @@
+//! Cached JSON Web Key Set with background refresh
+//!
+//! Keys are refreshed when the cache expires (per the JWKS response's
+//! `Cache-Control: max-age`, clamped to sane bounds) and on demand when a
+//! token arrives signed with an unknown `kid`, which is how a key rotation
+//! shows up before the scheduled refresh. On-demand refetches are rate
+//! limited so a flood of forged tokens cannot hammer the provider.
+
+use chrono::{DateTime, Utc};
+use jsonwebtoken::DecodingKey;
+use reqwest::header::{HeaderMap, CACHE_CONTROL};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::dependencies::{Backoff, Dependencies};
+
+/// Refresh when the provider sends no usable `Cache-Control`
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+/// Never refresh more often than this on schedule
+const MIN_TTL: Duration = Duration::from_secs(5 * 60);
+/// Never keep keys longer than this without refreshing
+const MAX_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Minimum time between refetches triggered by unknown `kid`s
+const UNKNOWN_KID_COOLDOWN: Duration = Duration::from_secs(30);
+/// Limits on one JWKS fetch; fetches hold the lock every unknown-`kid`
+/// lookup and the scheduled refresh wait on
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum JwksError {
+    #[error("failed to fetch JWKS: {0}")]
+    Fetch(#[from] reqwest::Error),
+    #[error("invalid key in JWKS: {0}")]
+    InvalidKey(#[from] jsonwebtoken::errors::Error),
+    #[error("unknown key ID '{0}'")]
+    UnknownKid(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct JwksResponse {
+    keys: Vec<JwkKey>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwkKey {
+    #[serde(rename = "kid")]
+    key_id: String,
+    #[serde(default)]
+    kty: Option<String>,
+    #[serde(rename = "n", default)]
+    modulus: Option<String>,
+    #[serde(rename = "e", default)]
+    exponent: Option<String>,
+}
+
+struct CachedKeys {
+    keys: HashMap<String, DecodingKey>,
+    expires_at: Instant,
+    refreshed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+struct Counters {
+    refreshes: AtomicU64,
+    refresh_failures: AtomicU64,
+    rotations: AtomicU64,
+    unknown_kid_refetches: AtomicU64,
+}
+
+/// Snapshot of the cache for health reporting
+#[derive(Debug, Clone, Serialize)]
+pub struct JwksMetrics {
+    pub key_count: usize,
+    pub refreshes: u64,
+    pub refresh_failures: u64,
+    pub rotations: u64,
+    pub unknown_kid_refetches: u64,
+    pub last_refreshed_at: Option<DateTime<Utc>>,
+}
+
+pub struct JwksCache {
+    url: String,
+    http: reqwest::Client,
+    cached: RwLock<CachedKeys>,
+    /// Serializes fetches and remembers when the last on-demand one ran
+    last_on_demand: tokio::sync::Mutex<Option<Instant>>,
+    counters: Counters,
+}
+
+impl JwksCache {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            // Fails only where `reqwest::Client::new` would panic too
+            http: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .timeout(FETCH_TIMEOUT)
+                .build()
+                .expect("failed to build JWKS HTTP client"),
+            cached: RwLock::new(CachedKeys {
+                keys: HashMap::new(),
+                expires_at: Instant::now(),
+                refreshed_at: None,
+            }),
+            last_on_demand: tokio::sync::Mutex::new(None),
+            counters: Counters::default(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn has_keys(&self) -> bool {
+        !self.cached.read().unwrap().keys.is_empty()
+    }
+
+    pub fn metrics(&self) -> JwksMetrics {
+        let cached = self.cached.read().unwrap();
+        JwksMetrics {
+            key_count: cached.keys.len(),
+            refreshes: self.counters.refreshes.load(Ordering::Relaxed),
+            refresh_failures: self.counters.refresh_failures.load(Ordering::Relaxed),
+            rotations: self.counters.rotations.load(Ordering::Relaxed),
+            unknown_kid_refetches: self.counters.unknown_kid_refetches.load(Ordering::Relaxed),
+            last_refreshed_at: cached.refreshed_at,
+        }
+    }
+
+    /// Look up the key for `kid`, refetching the set once if it is unknown
+    pub async fn key(&self, kid: &str) -> Result<DecodingKey, JwksError> {
+        if let Some(key) = self.cached_key(kid) {
+            return Ok(key);
+        }
+
+        {
+            let mut last_on_demand = self.last_on_demand.lock().await;
+
+            // Another request may have refetched while we waited for the lock
+            if let Some(key) = self.cached_key(kid) {
+                return Ok(key);
+            }
+
+            let cooling_down = last_on_demand
+                .is_some_and(|last| last.elapsed() < UNKNOWN_KID_COOLDOWN);
+            if cooling_down {
+                tracing::warn!("Unknown key ID '{}'; JWKS refetch rate limited", kid);
+                return Err(JwksError::UnknownKid(kid.to_string()));
+            }
+
+            *last_on_demand = Some(Instant::now());
+            self.counters.unknown_kid_refetches.fetch_add(1, Ordering::Relaxed);
+            tracing::info!("Unknown key ID '{}'; refetching JWKS from {}", kid, self.url);
+
+            if let Err(e) = self.fetch().await {
+                tracing::error!("On-demand JWKS refetch failed: {}", e);
+            }
+        }
+
+        self.cached_key(kid).ok_or_else(|| {
+            let cached = self.cached.read().unwrap();
+            tracing::error!(
+                "Unknown key ID '{}' in token header. Available keys: {:?}",
+                kid,
+                cached.keys.keys().collect::<Vec<_>>()
+            );
+            JwksError::UnknownKid(kid.to_string())
+        })
+    }
+
+    fn cached_key(&self, kid: &str) -> Option<DecodingKey> {
+        self.cached.read().unwrap().keys.get(kid).cloned()
+    }
+
+    /// Fetch the key set now, replacing the cached keys
+    pub async fn refresh(&self) -> Result<usize, JwksError> {
+        let _guard = self.last_on_demand.lock().await;
+        self.fetch().await
+    }
+
+    /// Time until the cached keys expire
+    pub fn time_to_expiry(&self) -> Duration {
+        self.cached
+            .read()
+            .unwrap()
+            .expires_at
+            .saturating_duration_since(Instant::now())
+    }
+
+    async fn fetch(&self) -> Result<usize, JwksError> {
+        tracing::info!("Fetching JWKS from: {}", self.url);
+
+        let result = self.fetch_keys().await;
+        let (keys, ttl) = match result {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                self.counters.refresh_failures.fetch_add(1, Ordering::Relaxed);
+                return Err(e);
+            }
+        };
+
+        self.counters.refreshes.fetch_add(1, Ordering::Relaxed);
+
+        let mut cached = self.cached.write().unwrap();
+
+        let old: BTreeSet<&String> = cached.keys.keys().collect();
+        let new: BTreeSet<&String> = keys.keys().collect();
+        if !old.is_empty() && old != new {
+            let added: Vec<_> = new.difference(&old).collect();
+            let removed: Vec<_> = old.difference(&new).collect();
+            self.counters.rotations.fetch_add(1, Ordering::Relaxed);
+            tracing::info!(
+                "JWKS key rotation detected at {}: added {:?}, removed {:?}",
+                self.url,
+                added,
+                removed
+            );
+        }
+
+        let count = keys.len();
+        *cached = CachedKeys {
+            keys,
+            expires_at: Instant::now() + ttl,
+            refreshed_at: Some(Utc::now()),
+        };
+
+        tracing::info!("Loaded {} JWKS keys; next refresh in {:?}", count, ttl);
+        Ok(count)
+    }
+
+    async fn fetch_keys(&self) -> Result<(HashMap<String, DecodingKey>, Duration), JwksError> {
+        let response = self.http.get(&self.url).send().await?.error_for_status()?;
+        let ttl = cache_ttl(response.headers());
+        let jwks_response: JwksResponse = response.json().await?;
+
+        let mut keys = HashMap::new();
+        for key in jwks_response.keys {
+            // Only RSA signing keys are supported; skip anything else
+            if key.kty.as_deref().is_some_and(|kty| kty != "RSA") {
+                continue;
+            }
+            let (Some(modulus), Some(exponent)) = (key.modulus, key.exponent) else {
+                continue;
+            };
+            let decoding_key = DecodingKey::from_rsa_components(&modulus, &exponent)?;
+            keys.insert(key.key_id, decoding_key);
+        }
+
+        Ok((keys, ttl))
+    }
+
+    /// Keep the cache fresh for the life of the process
+    ///
+    /// Marks the auth dependency up once keys are loaded. A failed scheduled
+    /// refresh keeps serving the cached keys and retries with backoff; auth
+    /// only goes down if there are no keys at all.
+    pub fn spawn_refresh(self: Arc<Self>, dependencies: Arc<Dependencies>) {
+        tokio::spawn(async move {
+            let mut backoff = Backoff::default();
+
+            loop {
+                match self.refresh().await {
+                    Ok(_) => {
+                        dependencies.auth.mark_up();
+                        backoff = Backoff::default();
+                        tokio::time::sleep(self.time_to_expiry()).await;
+                    }
+                    Err(e) => {
+                        let delay = backoff.next_delay();
+                        tracing::warn!("JWKS refresh from {} failed: {}. Retrying in {:?}", self.url, e, delay);
+                        if !self.has_keys() {
+                            dependencies.auth.mark_down();
+                        }
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// How long to cache a JWKS response, from its `Cache-Control` header
+fn cache_ttl(headers: &HeaderMap) -> Duration {
+    let Some(cache_control) = headers.get(CACHE_CONTROL).and_then(|v| v.to_str().ok()) else {
+        return DEFAULT_TTL;
+    };
+
+    let mut ttl = None;
+    for directive in cache_control.split(',').map(str::trim) {
+        let directive = directive.to_ascii_lowercase();
+        if directive == "no-store" || directive == "no-cache" {
+            return MIN_TTL;
+        }
+        if let Some(seconds) = directive.strip_prefix("max-age=") {
+            ttl = seconds.trim_matches('"').parse::<u64>().ok().map(Duration::from_secs);
+        }
+    }
+
+    ttl.unwrap_or(DEFAULT_TTL).clamp(MIN_TTL, MAX_TTL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Json, Router};
+    use reqwest::header::HeaderValue;
+    use std::sync::atomic::AtomicUsize;
+
+    fn ttl(cache_control: &str) -> Duration {
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_CONTROL, HeaderValue::from_str(cache_control).unwrap());
+        cache_ttl(&headers)
+    }
+
+    #[test]
+    fn ttl_follows_max_age_within_bounds() {
+        assert_eq!(cache_ttl(&HeaderMap::new()), DEFAULT_TTL);
+        assert_eq!(ttl("public, max-age=7200"), Duration::from_secs(7200));
+        assert_eq!(ttl("Max-Age=\"900\", must-revalidate"), Duration::from_secs(900));
+        assert_eq!(ttl("max-age=10"), MIN_TTL);
+        assert_eq!(ttl("max-age=31536000"), MAX_TTL);
+        assert_eq!(ttl("max-age=soon"), DEFAULT_TTL);
+        assert_eq!(ttl("public"), DEFAULT_TTL);
+    }
+
+    #[test]
+    fn uncacheable_responses_refresh_soonest() {
+        assert_eq!(ttl("no-store"), MIN_TTL);
+        assert_eq!(ttl("max-age=7200, no-cache"), MIN_TTL);
+        assert_eq!(ttl("No-Store, max-age=7200"), MIN_TTL);
+    }
+
+    /// A cache of `fixtures/test-jwks.json`, served locally, and the number
+    /// of fetches so far
+    async fn served_cache() -> (JwksCache, Arc<AtomicUsize>) {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let keys: serde_json::Value = serde_json::from_str(include_str!("../../fixtures/test-jwks.json")).unwrap();
+        let counter = fetches.clone();
+        let app = Router::new().route(
+            "/jwks.json",
+            get(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Json(keys)
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+
+        (JwksCache::new(format!("http://{}/jwks.json", addr)), fetches)
+    }
+
+    #[tokio::test]
+    async fn unknown_kid_refetches_at_most_once_per_cooldown() {
+        let (cache, fetches) = served_cache().await;
+        cache.refresh().await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        assert!(cache.key("test-key").await.is_ok());
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        assert!(matches!(cache.key("rotated").await, Err(JwksError::UnknownKid(_))));
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+        // Within the cooldown: rejected without a fetch
+        assert!(matches!(cache.key("forged").await, Err(JwksError::UnknownKid(_))));
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.metrics().unknown_kid_refetches, 1);
+
+        // Once the cooldown has passed, the next unknown kid refetches
+        *cache.last_on_demand.lock().await = Some(Instant::now() - UNKNOWN_KID_COOLDOWN - Duration::from_secs(1));
+        assert!(matches!(cache.key("rotated").await, Err(JwksError::UnknownKid(_))));
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+        assert_eq!(cache.metrics().unknown_kid_refetches, 2);
+    }
+}
@@ -1,132 +1,200 @@
 use axum::{
     extract::{Request, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
+    Json,
 };
 
 use crate::app_state::AppState;
-use crate::auth::user::AuthUser;
-use crate::db::connection::DatabaseConnection;
+use crate::auth::api_token::{self, ApiTokenError};
+use crate::auth::{AuthError, AuthUser, Identity};
+use crate::db::error::DbError;
+use crate::repository::{find_by_identity, find_or_create_by_identity, subject_hash};
+use crate::validation::DISPLAY_NAME;
 
 /// Extension type to store authenticated user in request
 #[derive(Clone)]
 pub struct AuthExtension(pub AuthUser);
 
-/// Middleware to verify Clerk JWT and lookup/create user profile
+/// Extension set by [`optional_auth_middleware`]: the viewer, or `None` for
+/// an anonymous request
+#[derive(Clone)]
+pub struct OptionalAuthExtension(pub Option<AuthUser>);
+
+/// Middleware to authenticate the bearer credential and lookup/create the
+/// user profile
+///
+/// Accepts session JWTs from the auth provider and `cyn_` API tokens.
+/// Credential problems answer 401 with an [`AuthError`] body.
 pub async fn auth_middleware(
     State(app_state): State<AppState>,
     mut request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    // Extract Authorization header
-    let auth_header = request
-        .headers()
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+) -> Result<Response, Response> {
+    let token = bearer_token(&request).ok_or_else(|| AuthError::MissingToken.into_response())?;
+    let user = authenticate(&app_state, token).await?;
 
-    // Check for "Bearer <token>" format
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+    // Insert the authenticated user into request extensions
+    request.extensions_mut().insert(AuthExtension(user));
 
-    // Verify the JWT with Clerk
-    let claims = app_state.clerk_jwks
-        .verify_token(token)
-        .await
-        .map_err(|e| {
-            tracing::error!("JWT verification failed: {}", e);
-            StatusCode::UNAUTHORIZED
+    Ok(next.run(request).await)
+}
+
+/// Middleware for routes that work anonymously but adapt to the viewer
+///
+/// Without an `Authorization` header the request continues with
+/// `OptionalAuthExtension(None)`. A header that is present must hold a valid
+/// credential: a bad or expired token still answers 401 so clients notice
+/// instead of silently browsing as anonymous. When authenticated,
+/// `AuthExtension` is inserted too, so scope and role extractors work.
+pub async fn optional_auth_middleware(
+    State(app_state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, Response> {
+    let viewer = if request.headers().contains_key(header::AUTHORIZATION) {
+        let token = bearer_token(&request).ok_or_else(|| {
+            AuthError::Malformed("expected 'Authorization: Bearer <token>'".to_string()).into_response()
         })?;
+        let user = authenticate(&app_state, token).await?;
+        request.extensions_mut().insert(AuthExtension(user.clone()));
+        Some(user)
+    } else {
+        None
+    };
+
+    request.extensions_mut().insert(OptionalAuthExtension(viewer));
+
+    Ok(next.run(request).await)
+}
+
+/// Authenticate a bearer credential, either an API token or a session JWT
+async fn authenticate(app_state: &AppState, token: &str) -> Result<AuthUser, Response> {
+    if api_token::is_api_token(token) {
+        authenticate_api_token(app_state, token).await
+    } else {
+        authenticate_jwt(app_state, token).await
+    }
+}
+
+/// Verify a session JWT with the configured provider and map it to a profile
+async fn authenticate_jwt(app_state: &AppState, token: &str) -> Result<AuthUser, Response> {
+    let identity = app_state.auth.verify(token).await.map_err(|e| {
+        tracing::warn!("JWT verification failed ({}): {}", e.code(), e);
+        e.into_response()
+    })?;
 
-    let clerk_id = claims.sub;
-    tracing::debug!("Authenticated Clerk user: {}", clerk_id);
+    tracing::debug!("Authenticated {} user: {}", identity.provider, identity.subject);
 
     // Look up or create the user profile
-    let user = lookup_or_create_profile(&app_state.db, &clerk_id, &claims.email, &claims.name)
+    lookup_or_create_profile(app_state, &identity)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to lookup/create profile: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .map_err(|e| match e {
+            ProvisionError::Deleted => {
+                tracing::warn!("Rejected token for deleted account: {}", identity.subject);
+                account_deleted()
+            }
+            ProvisionError::Db(e) => {
+                tracing::error!("Failed to lookup/create profile: {}", e);
+                e.status_code().into_response()
+            }
+        })
+}
 
-    // Insert the authenticated user into request extensions
-    request.extensions_mut().insert(AuthExtension(user));
+/// Resolve a personal access token to its owner with the token's scopes
+async fn authenticate_api_token(app_state: &AppState, token: &str) -> Result<AuthUser, Response> {
+    api_token::authenticate(app_state.api_tokens.as_ref(), app_state.profiles.as_ref(), token)
+        .await
+        .map_err(|e| match e {
+            ApiTokenError::Invalid(e) => {
+                tracing::warn!("API token rejected: {}", e);
+                e.into_response()
+            }
+            ApiTokenError::ProfileDeleted => account_deleted(),
+            ApiTokenError::Db(e) => {
+                tracing::error!("Failed to look up API token: {}", e);
+                e.status_code().into_response()
+            }
+        })
+}
 
-    Ok(next.run(request).await)
+/// The token from an `Authorization: Bearer <token>` header
+fn bearer_token(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// 403 for a valid credential whose account was deleted
+fn account_deleted() -> Response {
+    let body = serde_json::json!({
+        "error": "account_deleted",
+        "message": "This account has been deleted",
+    });
+    (StatusCode::FORBIDDEN, Json(body)).into_response()
 }
 
-/// Look up a profile by clerkId, or create a new one if it doesn't exist
-async fn lookup_or_create_profile(
-    db: &DatabaseConnection,
-    clerk_id: &str,
-    email: &Option<String>,
-    name: &Option<String>,
-) -> Result<AuthUser, Box<dyn std::error::Error>> {
-    let database = db.get_db();
-
-    // Try to find existing profile by clerkId
-    let query = format!("SELECT FROM Profile WHERE clerkId = '{}'", clerk_id);
-
-    match database.query(&query).send::<serde_json::Value>().await {
-        Ok(results) if !results.is_empty() => {
-            // Profile exists, extract userId
-            let profile = &results[0];
-            let user_id = profile["userId"]
-                .as_str()
-                .ok_or("Missing userId in profile")?
-                .to_string();
-
-            tracing::info!("Found existing profile: userId={}", user_id);
-
-            Ok(AuthUser::new(clerk_id.to_string(), user_id)
-                .with_email(email.clone())
-                .with_display_name(name.clone()))
+/// Why a verified identity could not be mapped to a usable profile
+enum ProvisionError {
+    /// The profile was tombstoned, or the account erased
+    Deleted,
+    Db(DbError),
+}
+
+impl From<DbError> for ProvisionError {
+    fn from(e: DbError) -> Self {
+        Self::Db(e)
+    }
+}
+
+/// Look up the profile linked to the identity, or create a new one if it
+/// doesn't exist
+///
+/// Identities are keyed by provider and subject, so a profile reached
+/// through one provider can be linked to another via `/api/v1/identities`.
+/// An identity of an erased account is refused instead of being given a new
+/// profile.
+async fn lookup_or_create_profile(app_state: &AppState, identity: &Identity) -> Result<AuthUser, ProvisionError> {
+    let profiles = app_state.profiles.as_ref();
+    let identities = app_state.identities.as_ref();
+    // Provider names are not validated by anyone else
+    let token_name = identity.name.as_deref().and_then(|name| DISPLAY_NAME.fit(name));
+
+    let existing = find_by_identity(profiles, identities, &identity.provider, &identity.subject).await?;
+    let profile = match existing {
+        Some(profile) => profile,
+        None => {
+            let hash = subject_hash(&identity.provider, &identity.subject);
+            if app_state.erasures.is_erased(&hash).await? {
+                return Err(ProvisionError::Deleted);
+            }
+
+            find_or_create_by_identity(profiles, identities, &identity.provider, &identity.subject, || {
+                token_name.clone().unwrap_or_else(|| "User".to_string())
+            })
+            .await?
         }
-        _ => {
-            // Profile doesn't exist, create a new one
-            let user_id = uuid::Uuid::new_v4().to_string();
-            let display_name = name.as_ref().unwrap_or(&"User".to_string()).clone();
-
-            tracing::info!(
-                "Creating new profile: userId={}, clerkId={}",
-                user_id,
-                clerk_id
-            );
-
-            // Create the profile
-            let create_query = format!(
-                "INSERT INTO Profile SET userId = '{}', clerkId = '{}', displayName = '{}', createdAt = sysdate(), updatedAt = sysdate()",
-                user_id, clerk_id, display_name
-            );
-
-            // Execute via HTTP API
-            let db_host = std::env::var("ARCADE_DB_HOST").unwrap_or_else(|_| "localhost".to_string());
-            let db_port = std::env::var("ARCADE_DB_PORT").unwrap_or_else(|_| "2480".to_string());
-            let db_user = std::env::var("ARCADE_DB_USER").unwrap_or_else(|_| "root".to_string());
-            let db_password = std::env::var("ARCADE_DB_PASSWORD").unwrap_or_else(|_| "".to_string());
-            let db_name = std::env::var("ARCADE_DB_NAME").unwrap_or_else(|_| "cynnycty".to_string());
-
-            let url = format!("http://{}:{}/api/v1/command/{}", db_host, db_port, db_name);
-            let payload = serde_json::json!({
-                "language": "sql",
-                "command": create_query
-            });
-
-            let client = reqwest::Client::new();
-            client
-                .post(&url)
-                .basic_auth(&db_user, Some(&db_password))
-                .json(&payload)
-                .send()
-                .await?;
-
-            tracing::info!("Profile created successfully");
-
-            Ok(AuthUser::new(clerk_id.to_string(), user_id)
-                .with_email(email.clone())
-                .with_display_name(Some(display_name)))
+    };
+
+    if profile.is_deleted() {
+        return Err(ProvisionError::Deleted);
+    }
+
+    tracing::debug!("Resolved profile: userId={}", profile.user_id);
+
+    let mut roles = profile.roles;
+    for role in &identity.roles {
+        if !roles.contains(role) {
+            roles.push(*role);
         }
     }
+
+    Ok(AuthUser::new(identity.provider.clone(), identity.subject.clone(), profile.user_id)
+        .with_email(identity.email.clone())
+        .with_display_name(token_name.or(profile.display_name))
+        .with_roles(roles))
 }
@@ -1,9 +1,22 @@
-// Authentication module for Clerk JWT verification
+// Authentication module: JWT verification through a pluggable provider
+// (Clerk by default, any OIDC issuer, or self-signed tokens in development)
 
+pub mod api_token;
 pub mod clerk;
+pub mod dev;
+pub mod error;
+pub mod jwks;
 pub mod middleware;
+pub mod oidc;
+pub mod provider;
+pub mod roles;
+pub mod scopes;
 pub mod user;
 
 pub use clerk::ClerkJwks;
-pub use middleware::auth_middleware;
+pub use error::AuthError;
+pub use middleware::{auth_middleware, optional_auth_middleware};
+pub use provider::{AuthProvider, Identity};
+pub use roles::{Admin, Moderator, RequireRole, Role};
+pub use scopes::{ReadProfile, RequireScope, RequireSession, Scope, WriteProfile};
 pub use user::AuthUser;
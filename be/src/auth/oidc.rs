@@ -0,0 +1,101 @@
+//! Generic OpenID Connect provider
+//!
+//! Verifies RS256/RS384/RS512 ID or access tokens from any issuer that
+//! publishes a JWKS, e.g. Auth0, Keycloak, Google or Cognito.
+
+use async_trait::async_trait;
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::clerk::matches_any;
+use crate::auth::error::AuthError;
+use crate::auth::jwks::{JwksCache, JwksMetrics};
+use crate::auth::provider::{AuthProvider, Identity};
+use crate::config::OidcConfig;
+use crate::dependencies::Dependencies;
+
+const ALGORITHMS: [Algorithm; 3] = [Algorithm::RS256, Algorithm::RS384, Algorithm::RS512];
+
+#[derive(Debug, Deserialize)]
+struct OidcClaims {
+    sub: String,
+    iss: String,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: Option<bool>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+pub struct OidcProvider {
+    name: String,
+    issuer: String,
+    audience: Option<String>,
+    leeway: Duration,
+    cache: Arc<JwksCache>,
+}
+
+impl OidcProvider {
+    pub fn new(config: &OidcConfig, leeway: Duration) -> Self {
+        Self {
+            name: config.name.clone(),
+            issuer: config.issuer.clone(),
+            audience: config.audience.clone(),
+            leeway,
+            cache: Arc::new(JwksCache::new(config.jwks_url.clone())),
+        }
+    }
+}
+
+#[async_trait]
+impl AuthProvider for OidcProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn verify(&self, token: &str) -> Result<Identity, AuthError> {
+        let header = decode_header(token)?;
+        if !ALGORITHMS.contains(&header.alg) {
+            return Err(AuthError::Malformed(format!("unsupported algorithm {:?}", header.alg)));
+        }
+
+        let kid = header
+            .kid
+            .ok_or_else(|| AuthError::Malformed("missing 'kid' in header".to_string()))?;
+        let decoding_key = self.cache.key(&kid).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.validate_nbf = true;
+        validation.leeway = self.leeway.as_secs();
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let claims = decode::<OidcClaims>(token, &decoding_key, &validation)?.claims;
+
+        if !matches_any(std::slice::from_ref(&self.issuer), &claims.iss) {
+            return Err(AuthError::WrongIssuer(claims.iss));
+        }
+
+        Ok(Identity {
+            subject: claims.sub,
+            provider: self.name.clone(),
+            email: claims.email,
+            email_verified: claims.email_verified.unwrap_or(false),
+            name: claims.name,
+            roles: Vec::new(),
+        })
+    }
+
+    fn start(&self, dependencies: Arc<Dependencies>) {
+        self.cache.clone().spawn_refresh(dependencies);
+    }
+
+    fn key_metrics(&self) -> Option<JwksMetrics> {
+        Some(self.cache.metrics())
+    }
+}
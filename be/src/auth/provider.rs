@@ -0,0 +1,79 @@
+//! Provider-neutral authentication
+//!
+//! `auth_middleware` only sees an [`AuthProvider`] and the [`Identity`] it
+//! returns, so Clerk can be swapped for any OIDC issuer through config.
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::auth::clerk::ClerkJwks;
+use crate::auth::dev::DevAuth;
+use crate::auth::error::AuthError;
+use crate::auth::jwks::JwksMetrics;
+use crate::auth::oidc::OidcProvider;
+use crate::auth::roles::Role;
+use crate::config::{AuthConfig, AuthProviderKind, ClerkConfig};
+use crate::dependencies::Dependencies;
+
+/// Who a verified token belongs to, as told by the provider
+#[derive(Debug, Clone, Serialize)]
+pub struct Identity {
+    /// The provider's stable user ID (`sub` claim)
+    pub subject: String,
+    /// Name of the provider that issued the token, e.g. "clerk"
+    pub provider: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub name: Option<String>,
+    /// Roles asserted by the provider, added to those stored on the profile
+    pub roles: Vec<Role>,
+}
+
+#[async_trait]
+pub trait AuthProvider: Send + Sync {
+    /// Short name recorded on identities, e.g. "clerk"
+    fn name(&self) -> &str;
+
+    /// Verify a bearer token and return the identity it carries
+    async fn verify(&self, token: &str) -> Result<Identity, AuthError>;
+
+    /// Start background work such as key refresh, reporting availability
+    /// on `dependencies.auth`
+    fn start(&self, dependencies: Arc<Dependencies>);
+
+    /// Signing key cache metrics for `/health`, if the provider has one
+    fn key_metrics(&self) -> Option<JwksMetrics> {
+        None
+    }
+}
+
+/// Build the provider selected by `auth.provider`
+pub fn from_config(auth: &AuthConfig, clerk: &ClerkConfig) -> Result<Arc<dyn AuthProvider>, String> {
+    match auth.provider {
+        AuthProviderKind::Clerk => {
+            let mut clerk_jwks = ClerkJwks::new(&clerk.publishable_key)?
+                .with_authorized_parties(clerk.authorized_parties.clone())
+                .with_leeway(auth.leeway)
+                .with_roles_from_metadata(clerk.roles_from_metadata);
+            if !clerk.allowed_issuers.is_empty() {
+                clerk_jwks = clerk_jwks.with_allowed_issuers(clerk.allowed_issuers.clone());
+            }
+            Ok(Arc::new(clerk_jwks))
+        }
+        AuthProviderKind::Oidc => {
+            let oidc = auth
+                .oidc
+                .as_ref()
+                .ok_or("auth.provider is \"oidc\" but no [oidc] settings are configured")?;
+            Ok(Arc::new(OidcProvider::new(oidc, auth.leeway)))
+        }
+        AuthProviderKind::Dev => {
+            let secret = auth
+                .dev_secret
+                .as_ref()
+                .ok_or("auth.provider is \"dev\" but no dev secret is configured")?;
+            Ok(Arc::new(DevAuth::new(secret.expose()).with_leeway(auth.leeway)))
+        }
+    }
+}
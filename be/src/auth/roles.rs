@@ -0,0 +1,240 @@
+//! Roles and the `RequireRole<R>` extractor
+//!
+//! Roles are stored on `Profile.roles` and, if enabled, also read from the
+//! Clerk session token's public metadata. `Admin` satisfies any role check.
+//!
+//! ```ignore
+//! async fn ban_user(RequireRole(admin, ..): RequireRole<Admin>) -> StatusCode { ... }
+//! ```
+
+use async_trait::async_trait;
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use crate::auth::error::AuthError;
+use crate::auth::middleware::AuthExtension;
+use crate::auth::scopes::ScopeRejection;
+use crate::auth::AuthUser;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// Whether holding `self` passes a check for `required`
+    pub fn satisfies(&self, required: Role) -> bool {
+        *self == required || *self == Role::Admin
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "moderator" => Ok(Role::Moderator),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("unknown role '{}'", other)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Role {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Read a stored role list, treating null as empty and skipping roles this
+/// build doesn't know (e.g. written by a newer version)
+pub fn deserialize_roles<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Role>, D::Error> {
+    let names = Option::<Vec<String>>::deserialize(deserializer)?.unwrap_or_default();
+    Ok(parse_roles(names.iter().map(String::as_str)))
+}
+
+/// Parse role names, ignoring unknown ones and duplicates
+pub fn parse_roles<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<Role> {
+    let mut roles = Vec::new();
+    for name in names {
+        match name.parse::<Role>() {
+            Ok(role) if !roles.contains(&role) => roles.push(role),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Ignoring role: {}", e),
+        }
+    }
+    roles.sort();
+    roles
+}
+
+/// Type-level role for [`RequireRole`]
+pub trait RoleMarker: Send + Sync + 'static {
+    const ROLE: Role;
+}
+
+pub struct Admin;
+
+impl RoleMarker for Admin {
+    const ROLE: Role = Role::Admin;
+}
+
+pub struct Moderator;
+
+impl RoleMarker for Moderator {
+    const ROLE: Role = Role::Moderator;
+}
+
+/// Extractor for the authenticated user, answering 403 unless they hold
+/// role `R` and signed in with a session (API tokens never carry roles)
+///
+/// Must run behind `auth_middleware`; without it every request is 401.
+pub struct RequireRole<R: RoleMarker>(pub AuthUser, pub PhantomData<R>);
+
+/// Rejection of [`RequireRole`]
+pub enum RoleRejection {
+    Unauthenticated,
+    Forbidden(Role),
+    SessionRequired,
+}
+
+impl IntoResponse for RoleRejection {
+    fn into_response(self) -> Response {
+        match self {
+            RoleRejection::Unauthenticated => AuthError::MissingToken.into_response(),
+            RoleRejection::SessionRequired => ScopeRejection::SessionRequired.into_response(),
+            RoleRejection::Forbidden(role) => {
+                let body = serde_json::json!({
+                    "error": "forbidden",
+                    "message": format!("This action requires the {} role", role),
+                    "required_role": role,
+                });
+                (StatusCode::FORBIDDEN, Json(body)).into_response()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync, R: RoleMarker> FromRequestParts<S> for RequireRole<R> {
+    type Rejection = RoleRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let AuthExtension(user) = parts
+            .extensions
+            .get::<AuthExtension>()
+            .cloned()
+            .ok_or(RoleRejection::Unauthenticated)?;
+
+        if !user.is_session() {
+            return Err(RoleRejection::SessionRequired);
+        }
+
+        if !user.has_role(R::ROLE) {
+            tracing::warn!("User {} lacks the {} role", user.user_id, R::ROLE);
+            return Err(RoleRejection::Forbidden(R::ROLE));
+        }
+
+        Ok(RequireRole(user, PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::scopes::Scope;
+    use axum::http::Request;
+
+    fn user(roles: &[Role]) -> AuthUser {
+        AuthUser::new("dev".to_string(), "subject".to_string(), "user-1".to_string()).with_roles(roles.to_vec())
+    }
+
+    async fn extract<R: RoleMarker>(user: Option<AuthUser>) -> Result<RequireRole<R>, RoleRejection> {
+        let (mut parts, ()) = Request::new(()).into_parts();
+        if let Some(user) = user {
+            parts.extensions.insert(AuthExtension(user));
+        }
+        RequireRole::<R>::from_request_parts(&mut parts, &()).await
+    }
+
+    #[test]
+    fn admin_satisfies_every_role() {
+        assert!(Role::Admin.satisfies(Role::Admin));
+        assert!(Role::Admin.satisfies(Role::Moderator));
+        assert!(Role::Moderator.satisfies(Role::Moderator));
+        assert!(!Role::Moderator.satisfies(Role::Admin));
+    }
+
+    #[tokio::test]
+    async fn role_combinations() {
+        let cases: [(&[Role], bool, bool); 4] = [
+            // held, passes Moderator, passes Admin
+            (&[], false, false),
+            (&[Role::Moderator], true, false),
+            (&[Role::Admin], true, true),
+            (&[Role::Moderator, Role::Admin], true, true),
+        ];
+
+        for (held, moderator, admin) in cases {
+            let as_moderator = extract::<Moderator>(Some(user(held))).await;
+            assert_eq!(as_moderator.is_ok(), moderator, "{:?} as moderator", held);
+            if !moderator {
+                assert!(matches!(as_moderator, Err(RoleRejection::Forbidden(Role::Moderator))));
+            }
+
+            let as_admin = extract::<Admin>(Some(user(held))).await;
+            assert_eq!(as_admin.is_ok(), admin, "{:?} as admin", held);
+            if !admin {
+                assert!(matches!(as_admin, Err(RoleRejection::Forbidden(Role::Admin))));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn unauthenticated_is_rejected() {
+        assert!(matches!(extract::<Moderator>(None).await, Err(RoleRejection::Unauthenticated)));
+    }
+
+    #[tokio::test]
+    async fn api_tokens_never_pass() {
+        let token_user = user(&[Role::Admin]).with_scopes(Scope::ALL.to_vec());
+
+        assert!(matches!(
+            extract::<Moderator>(Some(token_user.clone())).await,
+            Err(RoleRejection::SessionRequired)
+        ));
+        assert!(matches!(extract::<Admin>(Some(token_user)).await, Err(RoleRejection::SessionRequired)));
+    }
+
+    #[test]
+    fn rejection_statuses() {
+        let status = |rejection: RoleRejection| rejection.into_response().status();
+
+        assert_eq!(status(RoleRejection::Unauthenticated), StatusCode::UNAUTHORIZED);
+        assert_eq!(status(RoleRejection::Forbidden(Role::Admin)), StatusCode::FORBIDDEN);
+        assert_eq!(status(RoleRejection::SessionRequired), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,156 @@
+//! Scopes restricting what an API token may do
+//!
+//! Requests authenticated with a session JWT hold every scope; requests
+//! authenticated with an API token hold only the scopes granted to it.
+//! Handlers check with the `RequireScope<S>` extractor, or `RequireSession`
+//! for actions an API token must never perform (managing tokens, admin).
+
+use async_trait::async_trait;
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use crate::auth::error::AuthError;
+use crate::auth::middleware::AuthExtension;
+use crate::auth::AuthUser;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Scope {
+    #[serde(rename = "profile:read")]
+    ProfileRead,
+    #[serde(rename = "profile:write")]
+    ProfileWrite,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 2] = [Scope::ProfileRead, Scope::ProfileWrite];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::ProfileRead => "profile:read",
+            Scope::ProfileWrite => "profile:write",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| format!("unknown scope '{}'", s))
+    }
+}
+
+/// Read a stored scope list, treating null as empty and skipping scopes
+/// this build doesn't know
+pub fn deserialize_scopes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Scope>, D::Error> {
+    let names = Option::<Vec<String>>::deserialize(deserializer)?.unwrap_or_default();
+    Ok(names
+        .iter()
+        .filter_map(|name| name.parse::<Scope>().ok())
+        .collect())
+}
+
+/// Type-level scope for [`RequireScope`]
+pub trait ScopeMarker: Send + Sync + 'static {
+    const SCOPE: Scope;
+}
+
+pub struct ReadProfile;
+
+impl ScopeMarker for ReadProfile {
+    const SCOPE: Scope = Scope::ProfileRead;
+}
+
+pub struct WriteProfile;
+
+impl ScopeMarker for WriteProfile {
+    const SCOPE: Scope = Scope::ProfileWrite;
+}
+
+/// Rejection of [`RequireScope`] and [`RequireSession`]
+pub enum ScopeRejection {
+    Unauthenticated,
+    MissingScope(Scope),
+    SessionRequired,
+}
+
+impl IntoResponse for ScopeRejection {
+    fn into_response(self) -> Response {
+        let body = match self {
+            ScopeRejection::Unauthenticated => return AuthError::MissingToken.into_response(),
+            ScopeRejection::MissingScope(scope) => serde_json::json!({
+                "error": "insufficient_scope",
+                "message": format!("This API token lacks the {} scope", scope),
+                "required_scope": scope,
+            }),
+            ScopeRejection::SessionRequired => serde_json::json!({
+                "error": "session_required",
+                "message": "API tokens cannot perform this action; sign in instead",
+            }),
+        };
+
+        (StatusCode::FORBIDDEN, Json(body)).into_response()
+    }
+}
+
+fn auth_user(parts: &Parts) -> Result<AuthUser, ScopeRejection> {
+    parts
+        .extensions
+        .get::<AuthExtension>()
+        .map(|AuthExtension(user)| user.clone())
+        .ok_or(ScopeRejection::Unauthenticated)
+}
+
+/// Extractor for the authenticated user, answering 403 if they came with an
+/// API token lacking scope `S`
+pub struct RequireScope<S: ScopeMarker>(pub AuthUser, pub PhantomData<S>);
+
+#[async_trait]
+impl<St: Send + Sync, S: ScopeMarker> FromRequestParts<St> for RequireScope<S> {
+    type Rejection = ScopeRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &St) -> Result<Self, Self::Rejection> {
+        let user = auth_user(parts)?;
+
+        if !user.has_scope(S::SCOPE) {
+            return Err(ScopeRejection::MissingScope(S::SCOPE));
+        }
+
+        Ok(RequireScope(user, PhantomData))
+    }
+}
+
+/// Extractor for a user authenticated with a session JWT, not an API token
+pub struct RequireSession(pub AuthUser);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequireSession {
+    type Rejection = ScopeRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user = auth_user(parts)?;
+
+        if !user.is_session() {
+            return Err(ScopeRejection::SessionRequired);
+        }
+
+        Ok(RequireSession(user))
+    }
+}
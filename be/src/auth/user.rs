@@ -1,25 +1,42 @@
 use serde::{Deserialize, Serialize};
 
-/// Authenticated user with both clerkId and internal userId
+use crate::auth::roles::Role;
+use crate::auth::scopes::Scope;
+
+/// Authenticated user: the identity that signed in and the profile it maps to
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthUser {
-    /// Clerk's user ID
-    pub clerk_id: String,
+    /// Provider that verified the credential, e.g. `clerk`, or
+    /// [`API_TOKEN_PROVIDER`] for an API token
+    ///
+    /// [`API_TOKEN_PROVIDER`]: crate::auth::api_token::API_TOKEN_PROVIDER
+    pub provider: String,
+    /// The provider's ID for the user (`sub`); the token ID for an API token
+    pub subject: String,
     /// Our internal user ID (from Profile table)
     pub user_id: String,
     /// User's email
     pub email: Option<String>,
     /// User's display name
     pub display_name: Option<String>,
+    /// Roles from the profile and, if enabled, the auth provider
+    #[serde(default)]
+    pub roles: Vec<Role>,
+    /// Scopes of the API token used; `None` for a session, which holds all
+    #[serde(default)]
+    pub scopes: Option<Vec<Scope>>,
 }
 
 impl AuthUser {
-    pub fn new(clerk_id: String, user_id: String) -> Self {
+    pub fn new(provider: String, subject: String, user_id: String) -> Self {
         Self {
-            clerk_id,
+            provider,
+            subject,
             user_id,
             email: None,
             display_name: None,
+            roles: Vec::new(),
+            scopes: None,
         }
     }
 
@@ -32,4 +49,30 @@ impl AuthUser {
         self.display_name = display_name;
         self
     }
+
+    pub fn with_roles(mut self, roles: Vec<Role>) -> Self {
+        self.roles = roles;
+        self
+    }
+
+    /// Whether the user passes a check for `role`; admins pass every check
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.iter().any(|held| held.satisfies(role))
+    }
+
+    /// Restrict the user to an API token's scopes
+    pub fn with_scopes(mut self, scopes: Vec<Scope>) -> Self {
+        self.scopes = Some(scopes);
+        self
+    }
+
+    /// Whether the request was authenticated with a session rather than an
+    /// API token
+    pub fn is_session(&self) -> bool {
+        self.scopes.is_none()
+    }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.as_ref().is_none_or(|scopes| scopes.contains(&scope))
+    }
 }
@@ -0,0 +1,765 @@
+//! Application configuration
+//!
+//! Built once at startup from an optional TOML file overlaid with
+//! environment variables (environment wins), validated up front, and shared
+//! through `AppState`. Nothing else in the backend reads `std::env`.
+//!
+//! The file is read from `CONFIG_FILE`, or `config.toml` in the working
+//! directory if present:
+//!
+//! ```toml
+//! [server]
+//! host = "0.0.0.0"
+//! port = 3000
+//!
+//! [database]
+//! host = "localhost"
+//! port = 2480
+//! name = "cynnycty"
+//! user = "root"
+//! password = ""
+//! migrations_dir = "migrations"
+//!
+//! [auth]
+//! provider = "clerk"          # or "oidc", or "dev" for local development
+//! dev_secret = "..."          # only with provider = "dev", 32+ characters
+//! leeway_seconds = 60         # clock skew tolerated on exp/nbf
+//!
+//! [clerk]
+//! publishable_key = "pk_test_..."
+//! secret_key = "sk_test_..."
+//! webhook_secret = "whsec_..." # enables POST /api/v1/webhooks/clerk
+//! allowed_issuers = ["https://clerk.cynnycty.com"]  # default: from publishable_key
+//! authorized_parties = ["https://cynnycty.com", "http://localhost:5173"]
+//! roles_from_metadata = false # trust roles in the token's public metadata
+//!
+//! [oidc]                      # only with provider = "oidc"
+//! name = "auth0"
+//! issuer = "https://example.eu.auth0.com/"
+//! jwks_url = "https://example.eu.auth0.com/.well-known/jwks.json"
+//! audience = "https://api.cynnycty.com"
+//!
+//! [accounts]
+//! deletion_grace_days = 30    # time to cancel a deletion before erasure
+//!
+//! [profiles]
+//! avatar_hosts = ["img.clerk.com", "gravatar.com"]  # subdomains are allowed too
+//!
+//! [handles]
+//! change_cooldown_days = 30   # wait between handle changes
+//! redirect_days = 90          # how long an old handle redirects and stays held
+//!
+//! [gcp]
+//! project_id = "..."
+//! storage_bucket = "..."
+//! ```
+
+use serde::Deserialize;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::auth::clerk::{self, frontend_api_domain};
+use crate::auth::dev::MIN_SECRET_LEN;
+use crate::webhooks::svix::SvixVerifier;
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Clock skew tolerated on token `exp`/`nbf` unless configured
+pub const DEFAULT_LEEWAY_SECONDS: u64 = 60;
+/// Largest configurable leeway; more would keep expired tokens alive
+const MAX_LEEWAY_SECONDS: u64 = 300;
+
+const DEFAULT_DELETION_GRACE_DAYS: u64 = 30;
+const MAX_DELETION_GRACE_DAYS: u64 = 365;
+
+/// Hosts avatar URLs may point to unless configured
+const DEFAULT_AVATAR_HOSTS: &[&str] = &["img.clerk.com", "images.clerk.dev", "gravatar.com"];
+
+const DEFAULT_HANDLE_COOLDOWN_DAYS: u64 = 30;
+const DEFAULT_HANDLE_REDIRECT_DAYS: u64 = 90;
+const MAX_HANDLE_DAYS: u64 = 3650;
+
+/// A value that must not end up in logs
+#[derive(Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"***\"")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub clerk: ClerkConfig,
+    pub accounts: AccountsConfig,
+    pub profiles: ProfilesConfig,
+    pub handles: HandlesConfig,
+    pub gcp: GcpConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub host: IpAddr,
+    pub port: u16,
+}
+
+impl ServerConfig {
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::new(self.host, self.port)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    pub host: String,
+    pub port: u16,
+    pub name: String,
+    pub user: String,
+    pub password: Secret,
+    /// Relative to the working directory unless absolute
+    pub migrations_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthProviderKind {
+    Clerk,
+    Oidc,
+    /// Self-signed HS256 tokens for local development and tests
+    Dev,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub provider: AuthProviderKind,
+    /// Set when `provider` is `Oidc`
+    pub oidc: Option<OidcConfig>,
+    /// HS256 signing secret; set when `provider` is `Dev`
+    pub dev_secret: Option<Secret>,
+    /// Clock skew tolerated on `exp` and `nbf`
+    pub leeway: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    /// Provider name recorded on identities
+    pub name: String,
+    /// Expected `iss` claim
+    pub issuer: String,
+    pub jwks_url: String,
+    /// Expected `aud` claim; not checked if unset
+    pub audience: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClerkConfig {
+    pub publishable_key: String,
+    pub secret_key: Option<Secret>,
+    /// Svix signing secret (`whsec_...`) for Clerk webhooks
+    pub webhook_secret: Option<Secret>,
+    /// Accepted `iss` values; empty means the instance the publishable key
+    /// belongs to
+    pub allowed_issuers: Vec<String>,
+    /// Accepted `azp` values (frontend origins); empty accepts any
+    pub authorized_parties: Vec<String>,
+    /// Read roles from the session token's `metadata` claim
+    pub roles_from_metadata: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountsConfig {
+    /// Time between a deletion request and the account's erasure
+    pub deletion_grace: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfilesConfig {
+    /// Lowercase hosts an avatar URL may use; their subdomains are accepted
+    pub avatar_hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HandlesConfig {
+    /// Minimum time between two handle changes
+    pub change_cooldown: Duration,
+    /// How long a released handle redirects to its former owner, who is the
+    /// only one who can claim it meanwhile
+    pub redirect_period: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GcpConfig {
+    pub project_id: Option<String>,
+    pub storage_bucket: Option<String>,
+}
+
+/// Every problem found while loading the configuration
+#[derive(Debug)]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "invalid configuration ({} problem(s)):", self.problems.len())?;
+        for problem in &self.problems {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Shape of the optional TOML file; every field may be omitted
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    server: FileServer,
+    database: FileDatabase,
+    auth: FileAuth,
+    clerk: FileClerk,
+    oidc: FileOidc,
+    accounts: FileAccounts,
+    profiles: FileProfiles,
+    handles: FileHandles,
+    gcp: FileGcp,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileServer {
+    host: Option<String>,
+    port: Option<u16>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileDatabase {
+    host: Option<String>,
+    port: Option<u16>,
+    name: Option<String>,
+    user: Option<String>,
+    password: Option<Secret>,
+    migrations_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileAuth {
+    provider: Option<String>,
+    dev_secret: Option<Secret>,
+    leeway_seconds: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileOidc {
+    name: Option<String>,
+    issuer: Option<String>,
+    jwks_url: Option<String>,
+    audience: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileClerk {
+    publishable_key: Option<String>,
+    secret_key: Option<Secret>,
+    webhook_secret: Option<Secret>,
+    allowed_issuers: Option<Vec<String>>,
+    authorized_parties: Option<Vec<String>>,
+    roles_from_metadata: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileAccounts {
+    deletion_grace_days: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileProfiles {
+    avatar_hosts: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileHandles {
+    change_cooldown_days: Option<u64>,
+    redirect_days: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileGcp {
+    project_id: Option<String>,
+    storage_bucket: Option<String>,
+}
+
+impl AppConfig {
+    /// Load from the process environment and the optional config file
+    pub fn load() -> Result<Self, ConfigError> {
+        let (path, required) = match std::env::var("CONFIG_FILE") {
+            Ok(path) => (PathBuf::from(path), true),
+            Err(_) => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+        };
+
+        let file_contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => Some((path, contents)),
+            Err(e) if required || e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(ConfigError {
+                    problems: vec![format!("failed to read {}: {}", path.display(), e)],
+                });
+            }
+            Err(_) => None,
+        };
+
+        Self::from_sources(
+            |key| std::env::var(key).ok(),
+            file_contents
+                .as_ref()
+                .map(|(path, contents)| (path.as_path(), contents.as_str())),
+        )
+    }
+
+    /// Build from an environment lookup and optional `(path, TOML contents)`
+    pub fn from_sources(
+        env: impl Fn(&str) -> Option<String>,
+        file: Option<(&Path, &str)>,
+    ) -> Result<Self, ConfigError> {
+        let mut problems = Vec::new();
+
+        let file = match file {
+            Some((path, contents)) => toml::from_str::<FileConfig>(contents).unwrap_or_else(|e| {
+                problems.push(format!("{}: {}", path.display(), e));
+                FileConfig::default()
+            }),
+            None => FileConfig::default(),
+        };
+
+        // Empty environment variables count as unset, as in .env.example
+        let env = |key: &str| env(key).filter(|value| !value.trim().is_empty());
+
+        let host_text = env("HOST")
+            .or(file.server.host)
+            .unwrap_or_else(|| "0.0.0.0".to_string());
+        let host = host_text.parse::<IpAddr>().unwrap_or_else(|_| {
+            problems.push(format!("HOST: '{}' is not a valid IP address", host_text));
+            IpAddr::from([0, 0, 0, 0])
+        });
+
+        let port = parse_port(&mut problems, "PORT", env("PORT"), file.server.port, 3000);
+
+        let database = DatabaseConfig {
+            host: env("ARCADE_DB_HOST")
+                .or(file.database.host)
+                .unwrap_or_else(|| "localhost".to_string()),
+            port: parse_port(
+                &mut problems,
+                "ARCADE_DB_PORT",
+                env("ARCADE_DB_PORT"),
+                file.database.port,
+                2480,
+            ),
+            name: env("ARCADE_DB_NAME")
+                .or(file.database.name)
+                .unwrap_or_else(|| "cynnycty".to_string()),
+            user: env("ARCADE_DB_USER")
+                .or(file.database.user)
+                .unwrap_or_else(|| "root".to_string()),
+            password: env("ARCADE_DB_PASSWORD")
+                .map(Secret::new)
+                .or(file.database.password)
+                .unwrap_or_default(),
+            migrations_dir: env("MIGRATIONS_DIR")
+                .map(PathBuf::from)
+                .or(file.database.migrations_dir)
+                .unwrap_or_else(|| PathBuf::from("migrations")),
+        };
+
+        if database.host.trim().is_empty() {
+            problems.push("ARCADE_DB_HOST: must not be empty".to_string());
+        }
+        if database.name.trim().is_empty() {
+            problems.push("ARCADE_DB_NAME: must not be empty".to_string());
+        }
+
+        let provider_text = env("AUTH_PROVIDER")
+            .or(file.auth.provider)
+            .unwrap_or_else(|| "clerk".to_string());
+        let provider = match provider_text.trim().to_ascii_lowercase().as_str() {
+            "clerk" => AuthProviderKind::Clerk,
+            "oidc" => AuthProviderKind::Oidc,
+            "dev" => AuthProviderKind::Dev,
+            _ => {
+                problems.push(format!(
+                    "AUTH_PROVIDER: '{}' is not one of \"clerk\", \"oidc\", \"dev\"",
+                    provider_text
+                ));
+                AuthProviderKind::Clerk
+            }
+        };
+
+        let publishable_key = env("CLERK_PUBLISHABLE_KEY")
+            .or(file.clerk.publishable_key)
+            .unwrap_or_default();
+        if provider == AuthProviderKind::Clerk {
+            if publishable_key.is_empty() {
+                problems.push("CLERK_PUBLISHABLE_KEY: must be set".to_string());
+            } else if let Err(e) = frontend_api_domain(&publishable_key) {
+                problems.push(format!("CLERK_PUBLISHABLE_KEY: {}", e));
+            }
+        }
+
+        let oidc = if provider == AuthProviderKind::Oidc {
+            let issuer = env("OIDC_ISSUER").or(file.oidc.issuer).unwrap_or_default();
+            let jwks_url = env("OIDC_JWKS_URL").or(file.oidc.jwks_url).unwrap_or_default();
+            require_url(&mut problems, "OIDC_ISSUER", &issuer);
+            require_url(&mut problems, "OIDC_JWKS_URL", &jwks_url);
+
+            let name = env("OIDC_PROVIDER_NAME")
+                .or(file.oidc.name)
+                .unwrap_or_else(|| "oidc".to_string());
+            // Identities are keyed by provider name; sharing one would let
+            // OIDC subjects sign in to Clerk or dev accounts
+            if name == clerk::PROVIDER_NAME || name == "dev" {
+                problems.push(format!("OIDC_PROVIDER_NAME: \"{}\" is reserved for a built-in provider", name));
+            }
+
+            Some(OidcConfig {
+                name,
+                issuer,
+                jwks_url,
+                audience: env("OIDC_AUDIENCE").or(file.oidc.audience),
+            })
+        } else {
+            None
+        };
+
+        let dev_secret = if provider == AuthProviderKind::Dev {
+            let secret = env("DEV_AUTH_SECRET")
+                .map(Secret::new)
+                .or(file.auth.dev_secret)
+                .unwrap_or_default();
+            if secret.expose().len() < MIN_SECRET_LEN {
+                problems.push(format!(
+                    "DEV_AUTH_SECRET: must be at least {} characters when AUTH_PROVIDER is \"dev\"",
+                    MIN_SECRET_LEN
+                ));
+            }
+            Some(secret)
+        } else {
+            None
+        };
+
+        let leeway_seconds = match env("AUTH_LEEWAY_SECONDS") {
+            Some(text) => text.trim().parse::<u64>().unwrap_or_else(|_| {
+                problems.push(format!("AUTH_LEEWAY_SECONDS: '{}' is not a number of seconds", text));
+                DEFAULT_LEEWAY_SECONDS
+            }),
+            None => file.auth.leeway_seconds.unwrap_or(DEFAULT_LEEWAY_SECONDS),
+        };
+        if leeway_seconds > MAX_LEEWAY_SECONDS {
+            problems.push(format!(
+                "AUTH_LEEWAY_SECONDS: {} exceeds the maximum of {}",
+                leeway_seconds, MAX_LEEWAY_SECONDS
+            ));
+        }
+
+        let auth = AuthConfig {
+            provider,
+            oidc,
+            dev_secret,
+            leeway: Duration::from_secs(leeway_seconds),
+        };
+
+        let clerk = ClerkConfig {
+            publishable_key,
+            secret_key: env("CLERK_SECRET_KEY")
+                .map(Secret::new)
+                .or(file.clerk.secret_key),
+            webhook_secret: env("CLERK_WEBHOOK_SECRET")
+                .map(Secret::new)
+                .or(file.clerk.webhook_secret),
+            allowed_issuers: env("CLERK_ALLOWED_ISSUERS")
+                .map(|text| parse_list(&text))
+                .or(file.clerk.allowed_issuers)
+                .unwrap_or_default(),
+            authorized_parties: env("CLERK_AUTHORIZED_PARTIES")
+                .map(|text| parse_list(&text))
+                .or(file.clerk.authorized_parties)
+                .unwrap_or_default(),
+            roles_from_metadata: match env("CLERK_ROLES_FROM_METADATA") {
+                Some(text) => parse_bool(&text).unwrap_or_else(|| {
+                    problems.push(format!("CLERK_ROLES_FROM_METADATA: '{}' is not true or false", text));
+                    false
+                }),
+                None => file.clerk.roles_from_metadata.unwrap_or(false),
+            },
+        };
+
+        for issuer in &clerk.allowed_issuers {
+            if !is_http_url(issuer) {
+                problems.push(format!("CLERK_ALLOWED_ISSUERS: '{}' is not an http(s) URL", issuer));
+            }
+        }
+        for party in &clerk.authorized_parties {
+            if !is_http_url(party) {
+                problems.push(format!("CLERK_AUTHORIZED_PARTIES: '{}' is not an http(s) origin", party));
+            }
+        }
+
+        if let Some(secret) = &clerk.webhook_secret
+            && let Err(e) = SvixVerifier::new(secret.expose())
+        {
+            problems.push(format!("CLERK_WEBHOOK_SECRET: {}", e));
+        }
+
+        let accounts = AccountsConfig {
+            deletion_grace: parse_days(
+                &mut problems,
+                "ACCOUNT_DELETION_GRACE_DAYS",
+                env("ACCOUNT_DELETION_GRACE_DAYS"),
+                file.accounts.deletion_grace_days,
+                DEFAULT_DELETION_GRACE_DAYS,
+                MAX_DELETION_GRACE_DAYS,
+            ),
+        };
+
+        let profiles = ProfilesConfig {
+            avatar_hosts: env("AVATAR_ALLOWED_HOSTS")
+                .map(|text| parse_list(&text))
+                .or(file.profiles.avatar_hosts)
+                .unwrap_or_else(|| DEFAULT_AVATAR_HOSTS.iter().map(|host| host.to_string()).collect())
+                .into_iter()
+                .map(|host| host.to_ascii_lowercase())
+                .collect(),
+        };
+
+        let handles = HandlesConfig {
+            change_cooldown: parse_days(
+                &mut problems,
+                "HANDLE_CHANGE_COOLDOWN_DAYS",
+                env("HANDLE_CHANGE_COOLDOWN_DAYS"),
+                file.handles.change_cooldown_days,
+                DEFAULT_HANDLE_COOLDOWN_DAYS,
+                MAX_HANDLE_DAYS,
+            ),
+            redirect_period: parse_days(
+                &mut problems,
+                "HANDLE_REDIRECT_DAYS",
+                env("HANDLE_REDIRECT_DAYS"),
+                file.handles.redirect_days,
+                DEFAULT_HANDLE_REDIRECT_DAYS,
+                MAX_HANDLE_DAYS,
+            ),
+        };
+
+        let gcp = GcpConfig {
+            project_id: env("GCP_PROJECT_ID").or(file.gcp.project_id),
+            storage_bucket: env("GCP_STORAGE_BUCKET").or(file.gcp.storage_bucket),
+        };
+
+        if !problems.is_empty() {
+            return Err(ConfigError { problems });
+        }
+
+        Ok(Self {
+            server: ServerConfig { host, port },
+            database,
+            auth,
+            clerk,
+            accounts,
+            profiles,
+            handles,
+            gcp,
+        })
+    }
+}
+
+fn parse_port(
+    problems: &mut Vec<String>,
+    key: &str,
+    env_value: Option<String>,
+    file_value: Option<u16>,
+    default: u16,
+) -> u16 {
+    match env_value {
+        Some(text) => match text.trim().parse::<u16>() {
+            Ok(port) if port != 0 => port,
+            _ => {
+                problems.push(format!("{}: '{}' is not a valid port (1-65535)", key, text));
+                default
+            }
+        },
+        None => file_value.unwrap_or(default),
+    }
+}
+
+/// A whole number of days, at most `max`
+fn parse_days(
+    problems: &mut Vec<String>,
+    key: &str,
+    env_value: Option<String>,
+    file_value: Option<u64>,
+    default: u64,
+    max: u64,
+) -> Duration {
+    let days = match env_value {
+        Some(text) => text.trim().parse::<u64>().unwrap_or_else(|_| {
+            problems.push(format!("{}: '{}' is not a number of days", key, text));
+            default
+        }),
+        None => file_value.unwrap_or(default),
+    };
+    if days > max {
+        problems.push(format!("{}: {} exceeds the maximum of {}", key, days, max));
+    }
+
+    Duration::from_secs(days * 24 * 60 * 60)
+}
+
+fn require_url(problems: &mut Vec<String>, key: &str, value: &str) {
+    if value.is_empty() {
+        problems.push(format!("{}: must be set", key));
+    } else if !is_http_url(value) {
+        problems.push(format!("{}: '{}' is not an http(s) URL", key, value));
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    value.starts_with("https://") || value.starts_with("http://")
+}
+
+fn parse_bool(text: &str) -> Option<bool> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" => Some(true),
+        "false" | "0" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Split a comma-separated environment value, dropping empty entries
+fn parse_list(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEV_SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    /// Load from `vars` and an optional `config.toml` body
+    fn load(vars: &[(&str, &str)], file: Option<&str>) -> Result<AppConfig, ConfigError> {
+        AppConfig::from_sources(
+            |key| vars.iter().find(|(name, _)| *name == key).map(|(_, value)| value.to_string()),
+            file.map(|contents| (Path::new("config.toml"), contents)),
+        )
+    }
+
+    fn problems(vars: &[(&str, &str)], file: Option<&str>) -> Vec<String> {
+        load(vars, file).err().map(|e| e.problems).unwrap_or_default()
+    }
+
+    fn oidc(name: &str) -> Vec<String> {
+        problems(
+            &[
+                ("AUTH_PROVIDER", "oidc"),
+                ("OIDC_ISSUER", "https://example.eu.auth0.com/"),
+                ("OIDC_JWKS_URL", "https://example.eu.auth0.com/.well-known/jwks.json"),
+                ("OIDC_PROVIDER_NAME", name),
+            ],
+            None,
+        )
+    }
+
+    #[test]
+    fn defaults() {
+        let config = load(&[("AUTH_PROVIDER", "dev"), ("DEV_AUTH_SECRET", DEV_SECRET)], None).unwrap();
+
+        assert_eq!(config.server.port, 3000);
+        assert_eq!(config.database.migrations_dir, PathBuf::from("migrations"));
+        assert_eq!(config.auth.leeway, Duration::from_secs(DEFAULT_LEEWAY_SECONDS));
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let problems = problems(
+            &[
+                ("PORT", "0"),
+                ("AUTH_PROVIDER", "saml"),
+                ("CLERK_PUBLISHABLE_KEY", "pk_test_Y2xlcmsuZXhhbXBsZS5jb20k"),
+                ("AUTH_LEEWAY_SECONDS", "600"),
+                ("HANDLE_REDIRECT_DAYS", "soon"),
+            ],
+            None,
+        );
+
+        assert_eq!(problems.len(), 4, "{:#?}", problems);
+        for key in ["PORT", "AUTH_PROVIDER", "AUTH_LEEWAY_SECONDS", "HANDLE_REDIRECT_DAYS"] {
+            assert!(problems.iter().any(|problem| problem.starts_with(key)), "{} in {:#?}", key, problems);
+        }
+
+        let message = ConfigError { problems }.to_string();
+        assert!(message.starts_with("invalid configuration (4 problem(s)):"), "{}", message);
+    }
+
+    #[test]
+    fn unknown_file_keys_are_rejected() {
+        let vars = [("AUTH_PROVIDER", "dev"), ("DEV_AUTH_SECRET", DEV_SECRET)];
+
+        let problems = problems(&vars, Some("[server]\nhots = \"0.0.0.0\"\n"));
+        assert_eq!(problems.len(), 1, "{:#?}", problems);
+        assert!(problems[0].contains("unknown field `hots`"), "{}", problems[0]);
+
+        let problems = self::problems(&vars, Some("[servers]\nhost = \"0.0.0.0\"\n"));
+        assert!(problems[0].contains("unknown field `servers`"), "{}", problems[0]);
+
+        assert!(load(&vars, Some("[server]\nport = 8080\n")).is_ok());
+    }
+
+    #[test]
+    fn dev_secret_needs_32_characters() {
+        let short = &DEV_SECRET[..MIN_SECRET_LEN - 1];
+        let problems = problems(&[("AUTH_PROVIDER", "dev"), ("DEV_AUTH_SECRET", short)], None);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("DEV_AUTH_SECRET"), "{}", problems[0]);
+
+        assert!(self::problems(&[("AUTH_PROVIDER", "dev")], None)[0].starts_with("DEV_AUTH_SECRET"));
+        assert!(load(&[("AUTH_PROVIDER", "dev"), ("DEV_AUTH_SECRET", DEV_SECRET)], None).is_ok());
+    }
+
+    #[test]
+    fn built_in_provider_names_are_reserved_for_oidc() {
+        for name in ["clerk", "dev"] {
+            let problems = oidc(name);
+            assert_eq!(problems.len(), 1, "{:#?}", problems);
+            assert!(problems[0].starts_with("OIDC_PROVIDER_NAME"), "{}", problems[0]);
+        }
+
+        assert!(oidc("auth0").is_empty());
+    }
+}
@@ -1,62 +1,164 @@
-use arcadedb_rs::{ArcadeDB, Auth};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::config::DatabaseConfig;
+use crate::db::error::DbError;
+use crate::db::query::Query;
+use crate::dependencies::{Dependencies, Dependency};
+
+/// How often [`DatabaseConnection::spawn_readiness_probe`] checks ArcadeDB
+pub const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Handle to a single ArcadeDB database over its HTTP API
+///
+/// Cloning is cheap: the underlying `reqwest::Client` keeps one connection
+/// pool shared by every clone.
 #[derive(Clone)]
 pub struct DatabaseConnection {
-    pub client: Arc<ArcadeDB>,
+    http: reqwest::Client,
+    credentials: Arc<Credentials>,
+    base_url: String,
     pub database_name: String,
 }
 
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+#[derive(Deserialize)]
+struct ResultEnvelope<T> {
+    result: Vec<T>,
+}
+
 impl DatabaseConnection {
-    pub async fn new(
+    pub fn new(
         host: &str,
         port: u16,
         username: &str,
         password: &str,
         database_name: &str,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let url = format!("http://{}:{}", host, port);
+    ) -> Result<Self, DbError> {
+        let base_url = format!("http://{}:{}", host, port);
 
-        tracing::info!("Connecting to ArcadeDB at {}", url);
+        tracing::info!("Connecting to ArcadeDB at {}", base_url);
 
-        let client = ArcadeDB::builder()
-            .auth(Auth::basic(username, password))
-            .build(&url)
-            .await?;
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
 
         Ok(Self {
-            client: Arc::new(client),
+            http,
+            credentials: Arc::new(Credentials {
+                username: username.to_string(),
+                password: password.to_string(),
+            }),
+            base_url,
             database_name: database_name.to_string(),
         })
     }
 
-    pub async fn health_check(&self) -> Result<bool, Box<dyn std::error::Error>> {
-        // Simple health check by querying the database
-        let db = self.client.db(&self.database_name);
-
+    pub async fn health_check(&self) -> Result<(), DbError> {
         // Try a simple query to verify connection
-        let _result = db
-            .query("SELECT 1 as health")
-            .send::<serde_json::Value>()
+        self.query::<serde_json::Value>(&Query::new("SELECT 1 as health"))
             .await?;
 
-        Ok(true)
+        Ok(())
+    }
+
+    /// Whether the ArcadeDB server answers its `/api/v1/ready` endpoint
+    pub async fn ready(&self) -> Result<(), DbError> {
+        let url = format!("{}/api/v1/ready", self.base_url);
+        self.http.get(&url).send().await?.error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Check readiness once and record the outcome on `dependency`
+    pub async fn probe(&self, dependency: &Dependency) {
+        match self.ready().await {
+            Ok(()) => {
+                if !dependency.is_up() {
+                    tracing::info!("ArcadeDB is available again");
+                }
+                dependency.mark_up();
+            }
+            Err(e) => {
+                if dependency.is_up() {
+                    tracing::warn!("ArcadeDB became unavailable: {}", e);
+                }
+                dependency.mark_down();
+            }
+        }
+    }
+
+    /// Keep checking readiness every [`PROBE_INTERVAL`] for the life of the
+    /// process
+    ///
+    /// Start this once migrations have run: it marks the database down while
+    /// ArcadeDB is unreachable, so dependent routes answer 503, and back up
+    /// when it recovers.
+    pub fn spawn_readiness_probe(self, dependencies: Arc<Dependencies>) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(PROBE_INTERVAL).await;
+                self.probe(&dependencies.database).await;
+            }
+        });
+    }
+
+    /// Run a read-only statement via `/api/v1/query`
+    pub async fn query<T: DeserializeOwned>(&self, query: &Query) -> Result<Vec<T>, DbError> {
+        self.execute("query", query).await
+    }
+
+    /// Run a statement that may modify data via `/api/v1/command`
+    pub async fn command<T: DeserializeOwned>(&self, query: &Query) -> Result<Vec<T>, DbError> {
+        self.execute("command", query).await
     }
 
-    pub fn get_db(&self) -> arcadedb_rs::Database {
-        self.client.db(&self.database_name)
+    async fn execute<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        query: &Query,
+    ) -> Result<Vec<T>, DbError> {
+        let url = format!("{}/api/v1/{}/{}", self.base_url, endpoint, self.database_name);
+        let payload = serde_json::json!({
+            "language": "sql",
+            "command": query.sql(),
+            "params": query.params(),
+        });
+
+        let response = self
+            .http
+            .post(&url)
+            .basic_auth(&self.credentials.username, Some(&self.credentials.password))
+            .json(&payload)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            let error = DbError::from_response(status.as_u16(), &body);
+            tracing::debug!("ArcadeDB {} failed: {} ({})", endpoint, error, query.sql());
+            return Err(error);
+        }
+
+        let envelope: ResultEnvelope<T> = serde_json::from_str(&body)?;
+        Ok(envelope.result)
     }
 }
 
-pub async fn init_database() -> Result<DatabaseConnection, Box<dyn std::error::Error>> {
-    let host = std::env::var("ARCADE_DB_HOST").unwrap_or_else(|_| "localhost".to_string());
-    let port = std::env::var("ARCADE_DB_PORT")
-        .unwrap_or_else(|_| "2480".to_string())
-        .parse::<u16>()
-        .unwrap_or(2480);
-    let username = std::env::var("ARCADE_DB_USER").unwrap_or_else(|_| "root".to_string());
-    let password = std::env::var("ARCADE_DB_PASSWORD").unwrap_or_else(|_| "".to_string());
-    let database_name = std::env::var("ARCADE_DB_NAME").unwrap_or_else(|_| "cynnycty".to_string());
-
-    DatabaseConnection::new(&host, port, &username, &password, &database_name).await
+pub fn init_database(config: &DatabaseConfig) -> Result<DatabaseConnection, DbError> {
+    DatabaseConnection::new(
+        &config.host,
+        config.port,
+        &config.user,
+        config.password.expose(),
+        &config.name,
+    )
 }
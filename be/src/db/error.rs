@@ -0,0 +1,75 @@
+use axum::http::StatusCode;
+use serde::Deserialize;
+
+/// Errors returned by the ArcadeDB command executor
+#[derive(Debug, thiserror::Error)]
+pub enum DbError {
+    /// The requested record or type does not exist
+    #[error("not found: {0}")]
+    NotFound(String),
+    /// A unique index rejected the write, or a concurrent update won
+    #[error("conflict: {0}")]
+    Conflict(String),
+    /// The write violated a schema constraint (mandatory, type, min/max...)
+    #[error("constraint violation: {0}")]
+    ConstraintViolation(String),
+    /// ArcadeDB could not be reached or the connection failed mid-request
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+    /// Any other error reported by ArcadeDB
+    #[error("server error ({status}): {message}")]
+    Server { status: u16, message: String },
+    /// The response did not match the expected shape
+    #[error("failed to decode response: {0}")]
+    Decode(#[from] serde_json::Error),
+}
+
+impl DbError {
+    /// The HTTP status a handler should answer with for this error
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            DbError::NotFound(_) => StatusCode::NOT_FOUND,
+            DbError::Conflict(_) => StatusCode::CONFLICT,
+            DbError::ConstraintViolation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            DbError::Transport(_) => StatusCode::SERVICE_UNAVAILABLE,
+            DbError::Server { .. } | DbError::Decode(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Classify an error response body returned by ArcadeDB
+    ///
+    /// ArcadeDB answers failed commands with
+    /// `{"error": ..., "detail": ..., "exception": "com.arcadedb.exception.X"}`;
+    /// the exception class is the most reliable signal of what went wrong.
+    pub(crate) fn from_response(status: u16, body: &str) -> Self {
+        let parsed: ErrorBody = serde_json::from_str(body).unwrap_or_default();
+
+        let message = match (parsed.error, parsed.detail) {
+            (Some(error), Some(detail)) if error != detail => format!("{}: {}", error, detail),
+            (Some(error), _) => error,
+            (None, Some(detail)) => detail,
+            (None, None) => body.to_string(),
+        };
+
+        let exception = parsed.exception.unwrap_or_default();
+        let exception = exception.rsplit('.').next().unwrap_or_default();
+
+        match exception {
+            "DuplicatedKeyException" | "ConcurrentModificationException" => {
+                DbError::Conflict(message)
+            }
+            "ValidationException" => DbError::ConstraintViolation(message),
+            "RecordNotFoundException" => DbError::NotFound(message),
+            _ if status == 404 => DbError::NotFound(message),
+            _ if status == 409 => DbError::Conflict(message),
+            _ => DbError::Server { status, message },
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ErrorBody {
+    error: Option<String>,
+    detail: Option<String>,
+    exception: Option<String>,
+}
@@ -0,0 +1,362 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::db::connection::DatabaseConnection;
+use crate::db::error::DbError;
+use crate::db::query::Query;
+
+/// Statements creating the bookkeeping type that records applied migrations
+const SCHEMA_VERSION_STATEMENTS: &[&str] = &[
+    "CREATE DOCUMENT TYPE SchemaVersion IF NOT EXISTS",
+    "CREATE PROPERTY SchemaVersion.version IF NOT EXISTS INTEGER",
+    "CREATE PROPERTY SchemaVersion.name IF NOT EXISTS STRING",
+    "CREATE PROPERTY SchemaVersion.checksum IF NOT EXISTS STRING",
+    "CREATE PROPERTY SchemaVersion.appliedAt IF NOT EXISTS DATETIME",
+    "CREATE INDEX SchemaVersion_version_idx IF NOT EXISTS ON SchemaVersion (version) UNIQUE",
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("failed to read migrations from {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid migration file name '{0}' (expected NNNN_name.sql)")]
+    InvalidFileName(String),
+    #[error("migration files must be numbered 1..n without gaps or duplicates; expected {expected}, found {found} ({name})")]
+    OutOfSequence {
+        expected: u32,
+        found: u32,
+        name: String,
+    },
+    #[error("migration {version} ({name}) was never applied, but later migration {applied} was")]
+    Skipped { version: u32, name: String, applied: u32 },
+    #[error("migration {version} ({name}) was applied but its file is missing")]
+    MissingFile { version: u32, name: String },
+    #[error("migration {version} ({name}) was modified after being applied (checksum {applied} in database, {on_disk} on disk)")]
+    ChecksumMismatch {
+        version: u32,
+        name: String,
+        applied: String,
+        on_disk: String,
+    },
+    #[error("migration {version} ({name}) failed at statement {statement}: {source}")]
+    Statement {
+        version: u32,
+        name: String,
+        statement: usize,
+        source: DbError,
+    },
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
+
+impl MigrationError {
+    /// Whether retrying later could succeed
+    ///
+    /// Only ArcadeDB being unreachable is worth waiting out. Problems with
+    /// the files, a statement ArcadeDB rejects, or a database whose history
+    /// no longer matches them need a human.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            MigrationError::Db(DbError::Transport(_))
+                | MigrationError::Statement {
+                    source: DbError::Transport(_),
+                    ..
+                }
+        )
+    }
+}
+
+/// A migration file read from disk
+#[derive(Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub name: String,
+    pub checksum: String,
+    pub sql: String,
+}
+
+impl Migration {
+    fn parse(file_name: &str, sql: String) -> Result<Self, MigrationError> {
+        let invalid = || MigrationError::InvalidFileName(file_name.to_string());
+
+        let stem = file_name.strip_suffix(".sql").ok_or_else(invalid)?;
+        let (number, name) = stem.split_once('_').ok_or_else(invalid)?;
+        let version = number.parse::<u32>().map_err(|_| invalid())?;
+
+        Ok(Self {
+            version,
+            name: name.to_string(),
+            checksum: format!("{:x}", Sha256::digest(sql.as_bytes())),
+            sql,
+        })
+    }
+
+    /// Split the file into individual statements
+    ///
+    /// Statements are separated by `;`. `--` comments are dropped, and
+    /// semicolons inside single-quoted literals do not end a statement.
+    pub fn statements(&self) -> Vec<String> {
+        let mut statements = Vec::new();
+        let mut current = String::new();
+
+        for line in self.sql.lines() {
+            let mut in_literal = false;
+            let mut chars = line.chars().peekable();
+
+            while let Some(c) = chars.next() {
+                match c {
+                    '\'' => {
+                        in_literal = !in_literal;
+                        current.push(c);
+                    }
+                    '-' if !in_literal && chars.peek() == Some(&'-') => break,
+                    ';' if !in_literal => {
+                        statements.push(std::mem::take(&mut current));
+                    }
+                    _ => current.push(c),
+                }
+            }
+            current.push('\n');
+        }
+        statements.push(current);
+
+        statements
+            .into_iter()
+            .map(|statement| statement.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|statement| !statement.is_empty())
+            .collect()
+    }
+}
+
+/// A row of the `SchemaVersion` type
+#[derive(Debug, Deserialize)]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    pub checksum: String,
+}
+
+/// Read and order every migration file in `dir`
+pub fn load_migrations(dir: &Path) -> Result<Vec<Migration>, MigrationError> {
+    let io_error = |source| MigrationError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+
+    let mut migrations = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("sql") {
+            continue;
+        }
+
+        let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let sql = std::fs::read_to_string(&path).map_err(|source| MigrationError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        migrations.push(Migration::parse(file_name, sql)?);
+    }
+
+    migrations.sort_by_key(|migration| migration.version);
+
+    for (idx, migration) in migrations.iter().enumerate() {
+        let expected = idx as u32 + 1;
+        if migration.version != expected {
+            return Err(MigrationError::OutOfSequence {
+                expected,
+                found: migration.version,
+                name: migration.name.clone(),
+            });
+        }
+    }
+
+    Ok(migrations)
+}
+
+/// Migrations recorded in the database, ordered by version
+pub async fn applied_migrations(
+    db: &DatabaseConnection,
+) -> Result<Vec<AppliedMigration>, MigrationError> {
+    for statement in SCHEMA_VERSION_STATEMENTS {
+        db.command::<serde_json::Value>(&Query::new(*statement)).await?;
+    }
+
+    Ok(db
+        .query(&Query::new(
+            "SELECT version, name, checksum FROM SchemaVersion ORDER BY version",
+        ))
+        .await?)
+}
+
+/// Check that every applied migration still matches its file on disk, and
+/// that none was skipped
+///
+/// Returns the migrations that have not been applied yet.
+pub fn pending<'a>(
+    migrations: &'a [Migration],
+    applied: &[AppliedMigration],
+) -> Result<&'a [Migration], MigrationError> {
+    for record in applied {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.version == record.version)
+            .ok_or_else(|| MigrationError::MissingFile {
+                version: record.version,
+                name: record.name.clone(),
+            })?;
+
+        if migration.checksum != record.checksum {
+            return Err(MigrationError::ChecksumMismatch {
+                version: record.version,
+                name: record.name.clone(),
+                applied: record.checksum.clone(),
+                on_disk: migration.checksum.clone(),
+            });
+        }
+    }
+
+    // Later migrations may depend on a skipped one, so applying it now could
+    // do damage; someone has to look at the database
+    let latest = applied.iter().map(|record| record.version).max().unwrap_or(0);
+    if let Some(skipped) = migrations[..latest as usize]
+        .iter()
+        .find(|migration| !applied.iter().any(|record| record.version == migration.version))
+    {
+        return Err(MigrationError::Skipped {
+            version: skipped.version,
+            name: skipped.name.clone(),
+            applied: latest,
+        });
+    }
+
+    Ok(&migrations[latest as usize..])
+}
+
+/// Apply every pending migration in `dir` in order
+///
+/// Migrations are forward-only. Refuses to run anything if an applied
+/// migration is missing from disk or has been edited since it was applied,
+/// or if a migration was skipped.
+pub async fn run_migrations(db: &DatabaseConnection, dir: &Path) -> Result<usize, MigrationError> {
+    let migrations = load_migrations(dir)?;
+    let applied = applied_migrations(db).await?;
+    let pending = pending(&migrations, &applied)?;
+
+    if pending.is_empty() {
+        tracing::info!("Database schema is up to date (version {})", migrations.len());
+        return Ok(0);
+    }
+
+    tracing::info!(
+        "Applying {} migration(s) from {}",
+        pending.len(),
+        dir.display()
+    );
+
+    for migration in pending {
+        tracing::info!("Applying migration {} ({})", migration.version, migration.name);
+
+        for (idx, statement) in migration.statements().iter().enumerate() {
+            tracing::debug!("Executing statement {}: {}", idx + 1, statement);
+
+            db.command::<serde_json::Value>(&Query::new(statement.as_str()))
+                .await
+                .map_err(|source| MigrationError::Statement {
+                    version: migration.version,
+                    name: migration.name.clone(),
+                    statement: idx + 1,
+                    source,
+                })?;
+        }
+
+        let record = Query::new(
+            "INSERT INTO SchemaVersion SET version = :version, name = :name, checksum = :checksum, appliedAt = sysdate()",
+        )
+        .bind("version", migration.version)
+        .bind("name", migration.name.as_str())
+        .bind("checksum", migration.checksum.as_str());
+        db.command::<serde_json::Value>(&record).await?;
+
+        tracing::info!("✓ Migration {} applied", migration.version);
+    }
+
+    Ok(pending.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrations(count: u32) -> Vec<Migration> {
+        (1..=count)
+            .map(|version| Migration::parse(&format!("{:04}_step.sql", version), format!("-- {}", version)).unwrap())
+            .collect()
+    }
+
+    fn applied(migrations: &[Migration], versions: &[u32]) -> Vec<AppliedMigration> {
+        versions
+            .iter()
+            .map(|version| {
+                let migration = &migrations[*version as usize - 1];
+                AppliedMigration {
+                    version: migration.version,
+                    name: migration.name.clone(),
+                    checksum: migration.checksum.clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn pending_versions(migrations: &[Migration], versions: &[u32]) -> Result<Vec<u32>, MigrationError> {
+        let applied = applied(migrations, versions);
+        pending(migrations, &applied).map(|pending| pending.iter().map(|migration| migration.version).collect())
+    }
+
+    #[test]
+    fn pending_follow_the_latest_applied() {
+        let migrations = migrations(3);
+
+        assert_eq!(pending_versions(&migrations, &[]).unwrap(), [1, 2, 3]);
+        assert_eq!(pending_versions(&migrations, &[1, 2]).unwrap(), [3]);
+        assert!(pending_versions(&migrations, &[1, 2, 3]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn skipped_migration_is_an_error() {
+        let migrations = migrations(4);
+
+        let error = pending_versions(&migrations, &[1, 3]).unwrap_err();
+        assert!(
+            matches!(error, MigrationError::Skipped { version: 2, applied: 3, .. }),
+            "{:?}",
+            error
+        );
+        assert!(!error.is_transient());
+
+        let error = pending_versions(&migrations, &[4]).unwrap_err();
+        assert!(matches!(error, MigrationError::Skipped { version: 1, applied: 4, .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn edited_or_missing_files_are_errors() {
+        let migrations = migrations(2);
+        let mut records = applied(&migrations, &[1, 2]);
+        records[1].checksum = "0".repeat(64);
+        assert!(matches!(
+            pending(&migrations, &records),
+            Err(MigrationError::ChecksumMismatch { version: 2, .. })
+        ));
+
+        let records = applied(&self::migrations(3), &[1, 2, 3]);
+        assert!(matches!(
+            pending(&migrations, &records),
+            Err(MigrationError::MissingFile { version: 3, .. })
+        ));
+    }
+}
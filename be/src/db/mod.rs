@@ -2,4 +2,7 @@
 // This will contain ArcadeDB connection and query logic
 
 pub mod connection;
-pub mod schema;
+pub mod error;
+pub mod migrations;
+pub mod query;
+pub mod timestamp;
@@ -0,0 +1,35 @@
+use serde_json::{Map, Value};
+
+/// A SQL statement whose values are sent as named parameters
+///
+/// Values are never spliced into the SQL text. Reference them as `:name`
+/// in the statement and bind them with [`Query::bind`]; ArcadeDB receives
+/// them in the `params` object of the request payload.
+#[derive(Debug, Clone)]
+pub struct Query {
+    sql: String,
+    params: Map<String, Value>,
+}
+
+impl Query {
+    pub fn new(sql: impl Into<String>) -> Self {
+        Self {
+            sql: sql.into(),
+            params: Map::new(),
+        }
+    }
+
+    /// Bind a value to the `:name` placeholder
+    pub fn bind(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.params.insert(name.to_string(), value.into());
+        self
+    }
+
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    pub fn params(&self) -> &Map<String, Value> {
+        &self.params
+    }
+}
@@ -0,0 +1,38 @@
+//! Serde helpers for ArcadeDB `DATETIME` values
+//!
+//! Depending on server version and settings, ArcadeDB returns datetimes as
+//! epoch milliseconds or as strings in its `yyyy-MM-dd HH:mm:ss[.SSS]`
+//! format. Both are accepted and normalized to UTC.
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawTimestamp {
+    Millis(i64),
+    Text(String),
+}
+
+fn parse(raw: RawTimestamp) -> Option<DateTime<Utc>> {
+    match raw {
+        RawTimestamp::Millis(millis) => Utc.timestamp_millis_opt(millis).single(),
+        RawTimestamp::Text(text) => DateTime::parse_from_rfc3339(&text)
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok()
+            .or_else(|| {
+                NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M:%S%.f")
+                    .ok()
+                    .map(|naive| naive.and_utc())
+            }),
+    }
+}
+
+/// Deserialize an optional ArcadeDB datetime; missing, null or unparseable
+/// values become `None`
+pub fn deserialize_option<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<RawTimestamp>::deserialize(deserializer)?.and_then(parse))
+}
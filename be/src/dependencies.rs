@@ -0,0 +1,211 @@
+//! Tracking of external dependencies (ArcadeDB, the auth provider's keys)
+//!
+//! The server starts even when a dependency is unreachable. Background tasks
+//! keep retrying with exponential backoff and flip the dependency to `up`
+//! once it recovers; until then routes that need it answer 503. Reports only
+//! carry the status: error details go to the log, not to clients.
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    /// Not yet reached since startup
+    Starting,
+    Up,
+    Down,
+}
+
+/// Point-in-time view of one dependency, as reported by `/health`
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyReport {
+    pub status: DependencyStatus,
+    pub since: DateTime<Utc>,
+}
+
+pub struct Dependency {
+    report: RwLock<DependencyReport>,
+}
+
+impl Dependency {
+    fn new() -> Self {
+        Self {
+            report: RwLock::new(DependencyReport {
+                status: DependencyStatus::Starting,
+                since: Utc::now(),
+            }),
+        }
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.report.read().unwrap().status == DependencyStatus::Up
+    }
+
+    pub fn report(&self) -> DependencyReport {
+        self.report.read().unwrap().clone()
+    }
+
+    pub fn mark_up(&self) {
+        let mut report = self.report.write().unwrap();
+        if report.status != DependencyStatus::Up {
+            report.status = DependencyStatus::Up;
+            report.since = Utc::now();
+        }
+    }
+
+    pub fn mark_down(&self) {
+        let mut report = self.report.write().unwrap();
+        if report.status == DependencyStatus::Up {
+            report.status = DependencyStatus::Down;
+            report.since = Utc::now();
+        } else if report.status == DependencyStatus::Starting {
+            report.status = DependencyStatus::Down;
+        }
+    }
+}
+
+/// Status of every external dependency
+pub struct Dependencies {
+    pub database: Dependency,
+    pub auth: Dependency,
+}
+
+impl Default for Dependencies {
+    fn default() -> Self {
+        Self {
+            database: Dependency::new(),
+            auth: Dependency::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependenciesReport {
+    pub database: DependencyReport,
+    pub auth: DependencyReport,
+}
+
+impl Dependencies {
+    pub fn all_up(&self) -> bool {
+        self.database.is_up() && self.auth.is_up()
+    }
+
+    pub fn report(&self) -> DependenciesReport {
+        DependenciesReport {
+            database: self.database.report(),
+            auth: self.auth.report(),
+        }
+    }
+}
+
+/// Exponential backoff between retries, capped at `max`
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    next: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self { next: initial, max }
+    }
+
+    /// The delay to wait now; doubles the following one
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        delay
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
+/// Run `attempt` until it succeeds, recording each outcome on `dependency`
+///
+/// Returns `Err` without retrying only when `attempt` reports a fatal error
+/// (`Retry::Fatal`), e.g. a configuration problem retrying cannot fix.
+pub async fn retry_until_up<F, Fut, E>(name: &str, dependency: &Dependency, mut attempt: F) -> Result<(), E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), Retry<E>>>,
+    E: std::fmt::Display,
+{
+    let mut backoff = Backoff::default();
+
+    loop {
+        match attempt().await {
+            Ok(()) => {
+                dependency.mark_up();
+                tracing::info!("{} is available", name);
+                return Ok(());
+            }
+            Err(Retry::Fatal(e)) => {
+                dependency.mark_down();
+                return Err(e);
+            }
+            Err(Retry::Transient(e)) => {
+                let delay = backoff.next_delay();
+                tracing::warn!("{} unavailable: {}. Retrying in {:?}", name, e, delay);
+                dependency.mark_down();
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Outcome of a failed attempt passed to [`retry_until_up`]
+pub enum Retry<E> {
+    Transient(E),
+    Fatal(E),
+}
+
+/// Answer 503 until the database and the auth provider are both available
+pub async fn require_dependencies(
+    State(dependencies): State<Arc<Dependencies>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !dependencies.all_up() {
+        return unavailable(&dependencies);
+    }
+
+    next.run(request).await
+}
+
+/// Answer 503 until the database is available, for routes that never
+/// authenticate
+pub async fn require_database(
+    State(dependencies): State<Arc<Dependencies>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !dependencies.database.is_up() {
+        return unavailable(&dependencies);
+    }
+
+    next.run(request).await
+}
+
+fn unavailable(dependencies: &Dependencies) -> Response {
+    let body = serde_json::json!({
+        "error": "service_unavailable",
+        "message": "A backend dependency is unavailable; try again shortly",
+        "dependencies": dependencies.report(),
+    });
+    (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response()
+}
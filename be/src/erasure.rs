@@ -0,0 +1,166 @@
+//! Account deletion: scheduling, cancelling and erasure
+//!
+//! Deleting an account only schedules it: the profile keeps working until
+//! the grace period ends, so the user can change their mind. The purge job
+//! then erases every due account. It writes the [`ErasureRecord`] first, so
+//! the account's identities are refused from that moment on, and then
+//! removes everything keyed by the userId.
+//!
+//! Profiles are documents without edges and no content types exist yet;
+//! any user-owned type added later must be removed in [`erase_account`] and
+//! exported by [`build_archive`].
+//!
+//! [`ErasureRecord`]: crate::repository::ErasureRecord
+//! [`build_archive`]: crate::export::build_archive
+
+use chrono::Utc;
+use std::fmt;
+use std::time::Duration;
+
+use crate::app_state::AppState;
+use crate::auth::clerk;
+use crate::db::error::DbError;
+use crate::repository::{subject_hash, NewErasureRecord, Profile, ProfileRepository};
+
+/// How often the purge job looks for accounts due for erasure
+pub const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Who asked for an account to be deleted
+#[derive(Debug, Clone)]
+pub enum Requester {
+    /// The account's owner
+    User,
+    /// An admin, by userId
+    Admin(String),
+    /// Clerk reported the user deleted
+    Clerk,
+}
+
+impl fmt::Display for Requester {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Requester::User => f.write_str("self"),
+            Requester::Admin(user_id) => write!(f, "admin:{}", user_id),
+            Requester::Clerk => f.write_str("clerk"),
+        }
+    }
+}
+
+/// Schedule the profile's erasure after `grace`; a deletion that is already
+/// scheduled is left as it is
+pub async fn request_deletion(
+    profiles: &dyn ProfileRepository,
+    profile: Profile,
+    requester: &Requester,
+    grace: Duration,
+) -> Result<Profile, DbError> {
+    if profile.deletion_scheduled_for.is_some() {
+        return Ok(profile);
+    }
+
+    let scheduled_for = Utc::now() + chrono::Duration::seconds(grace.as_secs() as i64);
+    let scheduled = profiles
+        .schedule_deletion(&profile.user_id, &requester.to_string(), scheduled_for)
+        .await?;
+
+    tracing::info!(
+        "Deletion of userId={} requested by {}, scheduled for {}",
+        scheduled.user_id,
+        requester,
+        scheduled_for
+    );
+
+    Ok(scheduled)
+}
+
+/// Permanently remove an account and record its erasure
+///
+/// Safe to retry: a second run finds the existing record and removes
+/// whatever the first run left behind.
+pub async fn erase_account(app_state: &AppState, profile: &Profile) -> Result<(), DbError> {
+    let user_id = profile.user_id.as_str();
+
+    let mut subject_hashes: Vec<String> = app_state
+        .identities
+        .list_for_user(user_id)
+        .await?
+        .iter()
+        .map(|identity| subject_hash(&identity.provider, &identity.subject))
+        .collect();
+    // Profiles from before identities were tracked may only have a clerkId
+    if let Some(clerk_id) = &profile.clerk_id {
+        let hash = subject_hash(clerk::PROVIDER_NAME, clerk_id);
+        if !subject_hashes.contains(&hash) {
+            subject_hashes.push(hash);
+        }
+    }
+
+    let recorded = app_state
+        .erasures
+        .create(NewErasureRecord {
+            user_id: user_id.to_string(),
+            subject_hashes,
+            requested_by: profile.deletion_requested_by.clone(),
+            requested_at: profile.deletion_requested_at,
+        })
+        .await;
+    match recorded {
+        Ok(_) => {}
+        Err(DbError::Conflict(_)) => tracing::debug!("Erasure of userId={} already recorded", user_id),
+        Err(e) => return Err(e),
+    }
+
+    let exports = app_state.exports.delete_for_user(user_id).await?;
+    let handles = app_state.handle_history.delete_for_user(user_id).await?;
+    let api_tokens = app_state.api_tokens.delete_for_user(user_id).await?;
+    let identities = app_state.identities.delete_for_user(user_id).await?;
+    match app_state.profiles.delete(user_id).await {
+        Ok(()) | Err(DbError::NotFound(_)) => {}
+        Err(e) => return Err(e),
+    }
+
+    tracing::info!(
+        "Erased account userId={} ({} identities, {} API tokens, {} exports, {} released handles)",
+        user_id,
+        identities,
+        api_tokens,
+        exports,
+        handles
+    );
+
+    Ok(())
+}
+
+/// Erase every account whose grace period has ended; returns how many were
+/// erased
+pub async fn purge_due(app_state: &AppState) -> Result<usize, DbError> {
+    let due = app_state.profiles.due_for_deletion(Utc::now()).await?;
+
+    let mut erased = 0;
+    for profile in &due {
+        match erase_account(app_state, profile).await {
+            Ok(()) => erased += 1,
+            // Left scheduled, so the next run retries it
+            Err(e) => tracing::error!("Failed to erase userId={}: {}", profile.user_id, e),
+        }
+    }
+
+    Ok(erased)
+}
+
+/// Run [`purge_due`] every [`PURGE_INTERVAL`] while the database is up
+pub fn spawn_purge_job(app_state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            if app_state.dependencies.database.is_up() {
+                match purge_due(&app_state).await {
+                    Ok(0) => {}
+                    Ok(erased) => tracing::info!("Purge job erased {} account(s)", erased),
+                    Err(e) => tracing::warn!("Purge job failed to list due accounts: {}", e),
+                }
+            }
+
+            tokio::time::sleep(PURGE_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,236 @@
+//! Personal data export
+//!
+//! An export collects everything stored about a user into one JSON
+//! [`ExportArchive`]. Jobs run in the background: the request only records a
+//! pending [`ExportJob`], and the archive is stored on the job once built so
+//! it can be downloaded until [`ARCHIVE_TTL`] runs out.
+//!
+//! The archive format is versioned. `fixtures/export-v1.json` shows version
+//! 1 and `tests/export.rs` holds archives to its shape. A change that
+//! renames or removes a field needs a new [`FORMAT_VERSION`] and fixture;
+//! adding a field only adds it to the fixture.
+//!
+//! The archive covers the same user-owned data as erasure; see
+//! [`crate::erasure`] before adding a type.
+//!
+//! [`ExportJob`]: crate::repository::ExportJob
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::app_state::AppState;
+use crate::auth::{clerk, Role, Scope};
+use crate::db::error::DbError;
+
+/// Identifies the file as an export archive
+pub const FORMAT: &str = "cynnycty-export";
+
+/// Version of the archive layout
+pub const FORMAT_VERSION: u32 = 1;
+
+/// How long a finished archive can be downloaded
+pub const ARCHIVE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// An unfinished job older than this is assumed lost, e.g. to a restart, and
+/// no longer blocks starting a new one
+pub const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// How often expired archives are deleted
+pub const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Everything stored about one user
+#[derive(Debug, Serialize)]
+pub struct ExportArchive {
+    pub format: &'static str,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub user_id: String,
+    pub profile: ExportedProfile,
+    pub identities: Vec<ExportedIdentity>,
+    pub api_tokens: Vec<ExportedApiToken>,
+    pub released_handles: Vec<ExportedHandle>,
+}
+
+/// The profile's own fields
+///
+/// `Profile::version` is left out on purpose: it is a concurrency counter
+/// for edits, not data about the user.
+#[derive(Debug, Serialize)]
+pub struct ExportedProfile {
+    pub handle: Option<String>,
+    pub handle_changed_at: Option<DateTime<Utc>>,
+    pub display_name: Option<String>,
+    pub about_me: Option<String>,
+    pub avatar_url: Option<String>,
+    pub roles: Vec<Role>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deletion_requested_at: Option<DateTime<Utc>>,
+    pub deletion_scheduled_for: Option<DateTime<Utc>>,
+    pub deletion_requested_by: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedIdentity {
+    pub provider: String,
+    pub subject: String,
+    pub linked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedHandle {
+    pub handle: String,
+    pub released_at: Option<DateTime<Utc>>,
+    /// Until when it redirects to this user
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Token metadata; the hash is a credential and is left out
+#[derive(Debug, Serialize)]
+pub struct ExportedApiToken {
+    pub id: String,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Collect the user's data into an archive
+pub async fn build_archive(app_state: &AppState, user_id: &str) -> Result<ExportArchive, DbError> {
+    let profile = app_state
+        .profiles
+        .find_by_user_id(user_id)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("profile {}", user_id)))?;
+
+    let mut identities: Vec<ExportedIdentity> = app_state
+        .identities
+        .list_for_user(user_id)
+        .await?
+        .into_iter()
+        .map(|identity| ExportedIdentity {
+            provider: identity.provider,
+            subject: identity.subject,
+            linked_at: identity.linked_at,
+        })
+        .collect();
+    // A legacy profile's Clerk user ID counts as an identity, as in erasure
+    if let Some(clerk_id) = &profile.clerk_id
+        && !identities
+            .iter()
+            .any(|identity| identity.provider == clerk::PROVIDER_NAME && &identity.subject == clerk_id)
+    {
+        identities.push(ExportedIdentity {
+            provider: clerk::PROVIDER_NAME.to_string(),
+            subject: clerk_id.clone(),
+            linked_at: profile.created_at,
+        });
+    }
+
+    let api_tokens = app_state
+        .api_tokens
+        .list_for_user(user_id)
+        .await?
+        .into_iter()
+        .map(|token| ExportedApiToken {
+            id: token.token_id,
+            name: token.name,
+            prefix: token.prefix,
+            scopes: token.scopes,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+            revoked_at: token.revoked_at,
+        })
+        .collect();
+
+    let released_handles = app_state
+        .handle_history
+        .list_for_user(user_id)
+        .await?
+        .into_iter()
+        .map(|released| ExportedHandle {
+            handle: released.handle,
+            released_at: released.released_at,
+            expires_at: released.expires_at,
+        })
+        .collect();
+
+    Ok(ExportArchive {
+        format: FORMAT,
+        version: FORMAT_VERSION,
+        exported_at: Utc::now(),
+        user_id: profile.user_id,
+        profile: ExportedProfile {
+            handle: profile.handle,
+            handle_changed_at: profile.handle_changed_at,
+            display_name: profile.display_name,
+            about_me: profile.about_me,
+            avatar_url: profile.avatar_url,
+            roles: profile.roles,
+            created_at: profile.created_at,
+            updated_at: profile.updated_at,
+            deletion_requested_at: profile.deletion_requested_at,
+            deletion_scheduled_for: profile.deletion_scheduled_for,
+            deletion_requested_by: profile.deletion_requested_by,
+        },
+        identities,
+        api_tokens,
+        released_handles,
+    })
+}
+
+/// Build the archive for a pending job and store it on the job, or record
+/// why it failed
+pub async fn run_export(app_state: &AppState, export_id: &str, user_id: &str) -> Result<(), DbError> {
+    app_state.exports.mark_running(export_id).await?;
+
+    let archive = match build_archive(app_state, user_id).await {
+        Ok(archive) => serde_json::to_string_pretty(&archive).map_err(DbError::from),
+        Err(e) => Err(e),
+    };
+
+    match archive {
+        Ok(archive) => {
+            let expires_at = Utc::now() + chrono::Duration::seconds(ARCHIVE_TTL.as_secs() as i64);
+            app_state.exports.complete(export_id, archive, expires_at).await?;
+            tracing::info!("Export {} for userId={} is ready", export_id, user_id);
+        }
+        Err(e) => {
+            tracing::error!("Export {} for userId={} failed: {}", export_id, user_id, e);
+            app_state.exports.fail(export_id, &e.to_string()).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Run [`run_export`] in the background
+pub fn spawn_export(app_state: AppState, export_id: String, user_id: String) {
+    tokio::spawn(async move {
+        if let Err(e) = run_export(&app_state, &export_id, &user_id).await {
+            tracing::error!("Failed to update export {}: {}", export_id, e);
+        }
+    });
+}
+
+/// Delete expired archives every [`CLEANUP_INTERVAL`] while the database is up
+pub fn spawn_cleanup_job(app_state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            if app_state.dependencies.database.is_up() {
+                match app_state.exports.delete_expired(Utc::now()).await {
+                    Ok(0) => {}
+                    Ok(deleted) => tracing::info!("Deleted {} expired export(s)", deleted),
+                    Err(e) => tracing::warn!("Failed to delete expired exports: {}", e),
+                }
+            }
+
+            tokio::time::sleep(CLEANUP_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,228 @@
+//! User handles: validation, availability and changes
+//!
+//! A handle is compared without regard to case but stored as typed. When a
+//! user changes theirs, the old one goes to the handle history: it keeps
+//! redirecting to them for [`HandlesConfig::redirect_period`], and nobody
+//! else can claim it meanwhile. Changes are limited to one per
+//! [`HandlesConfig::change_cooldown`]; choosing a first handle or changing
+//! only its case is not limited.
+//!
+//! [`HandlesConfig::redirect_period`]: crate::config::HandlesConfig::redirect_period
+//! [`HandlesConfig::change_cooldown`]: crate::config::HandlesConfig::change_cooldown
+
+use chrono::{DateTime, Utc};
+
+use crate::app_state::AppState;
+use crate::db::error::DbError;
+use crate::repository::{NewHandleHistory, Profile};
+
+pub const MIN_LEN: usize = 3;
+pub const MAX_LEN: usize = 30;
+
+/// Handles nobody can take because they would look official or collide with
+/// routes
+const RESERVED: &[&str] = &[
+    "about", "admin", "administrator", "api", "app", "auth", "cynnycty", "help", "login", "logout", "mod",
+    "moderator", "null", "official", "profile", "profiles", "root", "security", "settings", "signin", "signup",
+    "staff", "support", "system", "undefined", "www",
+];
+
+/// Why a handle is not acceptable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum HandleError {
+    #[error("handle must be at least {MIN_LEN} characters")]
+    TooShort,
+    #[error("handle must be at most {MAX_LEN} characters")]
+    TooLong,
+    #[error("handle may only contain letters, digits and underscores")]
+    InvalidCharacters,
+    #[error("handle must start with a letter")]
+    MustStartWithLetter,
+    #[error("handle is reserved")]
+    Reserved,
+}
+
+impl HandleError {
+    /// Stable machine-readable code for response bodies
+    pub fn code(&self) -> &'static str {
+        match self {
+            HandleError::TooShort => "too_short",
+            HandleError::TooLong => "too_long",
+            HandleError::InvalidCharacters => "invalid_characters",
+            HandleError::MustStartWithLetter => "must_start_with_letter",
+            HandleError::Reserved => "reserved",
+        }
+    }
+}
+
+/// Check a handle against the rules: 3-30 ASCII letters, digits or
+/// underscores, starting with a letter, and not reserved
+pub fn validate(handle: &str) -> Result<(), HandleError> {
+    if !handle.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(HandleError::InvalidCharacters);
+    }
+    if handle.len() < MIN_LEN {
+        return Err(HandleError::TooShort);
+    }
+    if handle.len() > MAX_LEN {
+        return Err(HandleError::TooLong);
+    }
+    if !handle.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(HandleError::MustStartWithLetter);
+    }
+    if RESERVED.contains(&handle.to_ascii_lowercase().as_str()) {
+        return Err(HandleError::Reserved);
+    }
+
+    Ok(())
+}
+
+/// Why a valid handle can't be taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unavailable {
+    Invalid(HandleError),
+    /// Another profile has it, or released it recently
+    Taken,
+}
+
+impl Unavailable {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Unavailable::Invalid(e) => e.code(),
+            Unavailable::Taken => "taken",
+        }
+    }
+}
+
+/// Whether `user_id` (or anyone, for `None`) could take `handle` now
+pub async fn availability(
+    app_state: &AppState,
+    handle: &str,
+    user_id: Option<&str>,
+) -> Result<Result<(), Unavailable>, DbError> {
+    if let Err(e) = validate(handle) {
+        return Ok(Err(Unavailable::Invalid(e)));
+    }
+
+    let is_other = |owner: &str| user_id != Some(owner);
+
+    if let Some(holder) = app_state.profiles.find_by_handle(handle).await?
+        && is_other(&holder.user_id)
+    {
+        return Ok(Err(Unavailable::Taken));
+    }
+    if let Some(released) = app_state.handle_history.find_active(handle, Utc::now()).await?
+        && is_other(&released.user_id)
+    {
+        return Ok(Err(Unavailable::Taken));
+    }
+
+    Ok(Ok(()))
+}
+
+/// Why a handle change was refused
+#[derive(Debug, thiserror::Error)]
+pub enum ChangeError {
+    #[error("handle is unavailable: {}", .0.code())]
+    Unavailable(Unavailable),
+    #[error("handle was changed too recently; next change allowed at {0}")]
+    Cooldown(DateTime<Utc>),
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
+
+/// Give `profile` the handle `handle`, recording the one it replaces
+pub async fn change_handle(app_state: &AppState, profile: Profile, handle: &str) -> Result<Profile, ChangeError> {
+    let now = Utc::now();
+    let current = profile.handle.clone();
+
+    if current.as_deref() == Some(handle) {
+        return Ok(profile);
+    }
+
+    validate(handle).map_err(|e| ChangeError::Unavailable(Unavailable::Invalid(e)))?;
+
+    // Changing only the case keeps the handle; it is neither limited nor
+    // released
+    let case_only = current.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(handle));
+    if !case_only {
+        if current.is_some()
+            && let Some(changed_at) = profile.handle_changed_at
+        {
+            let next_change_at = changed_at + to_chrono(app_state.config.handles.change_cooldown);
+            if next_change_at > now {
+                return Err(ChangeError::Cooldown(next_change_at));
+            }
+        }
+
+        availability(app_state, handle, Some(&profile.user_id))
+            .await?
+            .map_err(ChangeError::Unavailable)?;
+    }
+
+    let updated = match app_state.profiles.set_handle(&profile.user_id, handle).await {
+        Ok(updated) => updated,
+        // Someone took it since the availability check
+        Err(DbError::Conflict(_)) => return Err(ChangeError::Unavailable(Unavailable::Taken)),
+        Err(e) => return Err(e.into()),
+    };
+
+    if let Some(old) = current
+        && !case_only
+    {
+        let released = app_state
+            .handle_history
+            .record(NewHandleHistory {
+                handle: old.clone(),
+                user_id: profile.user_id.clone(),
+                expires_at: now + to_chrono(app_state.config.handles.redirect_period),
+            })
+            .await;
+        // The change itself succeeded; a missing redirect is not worth failing it
+        if let Err(e) = released {
+            tracing::error!("Failed to record released handle {} of userId={}: {}", old, profile.user_id, e);
+        }
+    }
+
+    tracing::info!("userId={} changed handle to {}", profile.user_id, handle);
+
+    Ok(updated)
+}
+
+fn to_chrono(duration: std::time::Duration) -> chrono::Duration {
+    chrono::Duration::seconds(duration.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_handles() {
+        for handle in ["ada", "Ada_Lovelace", "a12", &"a".repeat(MAX_LEN), "Admin_2"] {
+            assert_eq!(validate(handle), Ok(()), "{}", handle);
+        }
+    }
+
+    #[test]
+    fn invalid_handles() {
+        let cases = [
+            ("ab", HandleError::TooShort),
+            ("", HandleError::TooShort),
+            (&"a".repeat(MAX_LEN + 1), HandleError::TooLong),
+            ("ada-l", HandleError::InvalidCharacters),
+            ("ada l", HandleError::InvalidCharacters),
+            ("adé", HandleError::InvalidCharacters),
+            ("ａｄａ", HandleError::InvalidCharacters),
+            ("1ada", HandleError::MustStartWithLetter),
+            ("_ada", HandleError::MustStartWithLetter),
+            ("admin", HandleError::Reserved),
+            ("API", HandleError::Reserved),
+            ("Support", HandleError::Reserved),
+        ];
+
+        for (handle, error) in cases {
+            assert_eq!(validate(handle), Err(error), "{}", handle);
+        }
+    }
+}
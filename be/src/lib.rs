@@ -0,0 +1,19 @@
+// Cynnycty backend library
+// The binary in main.rs wires these modules together; tests can build the
+// router directly from here
+
+pub mod app_state;
+pub mod auth;
+pub mod config;
+pub mod db;
+pub mod dependencies;
+pub mod erasure;
+pub mod export;
+pub mod handle;
+pub mod patch;
+pub mod repository;
+pub mod routes;
+#[cfg(feature = "test-support")]
+pub mod testing;
+pub mod validation;
+pub mod webhooks;
@@ -1,24 +1,17 @@
-mod routes;
-mod db;
-mod auth;
-mod app_state;
-
-use axum::{
-    middleware,
-    routing::{get, put},
-    Router,
-};
-use std::net::SocketAddr;
 use std::sync::Arc;
-use tower_http::cors::{CorsLayer, Any};
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use routes::health::health_check;
-use routes::database::database_health_check;
-use routes::profiles::{get_current_profile, update_current_profile};
-use db::connection::init_database;
-use db::schema::{init_schema, is_schema_initialized};
-use auth::{ClerkJwks, auth_middleware};
-use app_state::AppState;
+use cynnycty_backend::app_state::AppState;
+use cynnycty_backend::auth;
+use cynnycty_backend::auth::dev::DevAuth;
+use cynnycty_backend::config::{AppConfig, AuthProviderKind};
+use cynnycty_backend::db::connection::init_database;
+use cynnycty_backend::db::migrations::run_migrations;
+use cynnycty_backend::dependencies::{retry_until_up, Dependencies, Retry};
+use cynnycty_backend::erasure;
+use cynnycty_backend::export;
+use cynnycty_backend::repository::Repositories;
+use cynnycty_backend::routes;
 
 #[tokio::main]
 async fn main() {
@@ -34,88 +27,170 @@ async fn main() {
     // Load environment variables
     dotenv::dotenv().ok();
 
-    // Initialize database connection
-    let db = match init_database().await {
-        Ok(db) => {
-            tracing::info!("Database connection established successfully");
-            db
+    let command = match parse_command(std::env::args().skip(1).collect()) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
         }
+    };
+
+    // Load and validate configuration; report every problem at once
+    let config = match AppConfig::load() {
+        Ok(config) => Arc::new(config),
         Err(e) => {
-            tracing::warn!("Failed to connect to database: {}. Server will start without database connection.", e);
-            tracing::info!("Database health check endpoint will be unavailable");
-            // For now, we'll panic. In production, you might want to continue without DB
-            panic!("Database connection required for startup");
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
     };
 
-    // Initialize database schema if needed
-    match is_schema_initialized(&db).await {
-        Ok(initialized) => {
-            if !initialized {
-                tracing::info!("Schema not initialized, running schema setup...");
-                if let Err(e) = init_schema(&db).await {
-                    tracing::error!("Failed to initialize schema: {}", e);
-                    panic!("Schema initialization failed");
-                }
-            } else {
-                tracing::info!("Database schema already initialized");
+    // `mint-token` only needs the dev secret, not the database
+    if let Command::MintToken { subject, email, name, ttl } = command {
+        let Some(secret) = config.auth.dev_secret.as_ref() else {
+            eprintln!("mint-token requires AUTH_PROVIDER=dev and DEV_AUTH_SECRET");
+            std::process::exit(1);
+        };
+        match DevAuth::new(secret.expose()).mint(&subject, email, name, ttl) {
+            Ok((token, _)) => println!("{}", token),
+            Err(e) => {
+                eprintln!("Failed to mint token: {}", e);
+                std::process::exit(1);
             }
         }
+        return;
+    }
+
+    let migrate_only = matches!(command, Command::Migrate);
+
+    // Build the database client; this does not contact ArcadeDB yet
+    let db = match init_database(&config.database) {
+        Ok(db) => db,
         Err(e) => {
-            tracing::warn!("Could not check schema status: {}, attempting initialization...", e);
-            if let Err(e) = init_schema(&db).await {
-                tracing::error!("Failed to initialize schema: {}", e);
-                panic!("Schema initialization failed");
+            tracing::error!("Failed to create database client: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if migrate_only {
+        match run_migrations(&db, &config.database.migrations_dir).await {
+            Ok(applied) => tracing::info!("Applied {} migration(s)", applied),
+            Err(e) => {
+                tracing::error!("Failed to migrate database schema: {}", e);
+                std::process::exit(1);
             }
         }
+        return;
     }
 
-    // Initialize Clerk JWKS for JWT verification
-    let publishable_key = std::env::var("CLERK_PUBLISHABLE_KEY")
-        .expect("CLERK_PUBLISHABLE_KEY must be set");
+    let dependencies = Arc::new(Dependencies::default());
+
+    // Connect to ArcadeDB and bring the schema up to date in the background.
+    // The server runs degraded while ArcadeDB is unreachable, but refuses to
+    // keep running if a migration fails or applied migrations no longer
+    // match the files on disk. Afterwards a probe keeps tracking whether
+    // ArcadeDB is reachable.
+    {
+        let db = db.clone();
+        let dependencies = dependencies.clone();
+        let migrations_dir = config.database.migrations_dir.clone();
+        tokio::spawn(async move {
+            let (db, migrations_dir) = (&db, &migrations_dir);
+            let result = retry_until_up("ArcadeDB", &dependencies.database, move || async move {
+                match run_migrations(db, migrations_dir).await {
+                    Ok(_) => Ok(()),
+                    Err(e) if e.is_transient() => Err(Retry::Transient(e)),
+                    Err(e) => Err(Retry::Fatal(e)),
+                }
+            })
+            .await;
+
+            if let Err(e) = result {
+                tracing::error!("Failed to migrate database schema: {}", e);
+                std::process::exit(1);
+            }
 
-    let clerk_jwks = Arc::new(
-        ClerkJwks::new(publishable_key)
-            .await
-            .expect("Failed to initialize Clerk JWKS"),
-    );
-    tracing::info!("Clerk JWKS initialized successfully");
+            db.clone().spawn_readiness_probe(dependencies.clone());
+        });
+    }
+
+    // Keep the auth provider's signing keys fresh in the background; auth
+    // stays down until the first fetch succeeds
+    let auth = match auth::provider::from_config(&config.auth, &config.clerk) {
+        Ok(auth) => auth,
+        Err(e) => {
+            tracing::error!("Invalid auth configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if config.auth.provider == AuthProviderKind::Dev {
+        tracing::warn!("Dev auth is enabled: self-signed tokens are accepted. Never use this in production");
+    }
+    tracing::info!("Using auth provider '{}'", auth.name());
+    auth.start(dependencies.clone());
 
     // Create shared app state
-    let app_state = AppState::new(db.clone(), clerk_jwks);
-
-    // Public routes (no auth required)
-    let public_routes = Router::new()
-        .route("/health", get(health_check))
-        .route("/api/v1/health", get(health_check))
-        .route("/api/v1/db/health", get(database_health_check))
-        .with_state(db);
-
-    // Protected routes (auth required)
-    let protected_routes = Router::new()
-        .route("/api/v1/profiles/me", get(get_current_profile))
-        .route("/api/v1/profiles/me", put(update_current_profile))
-        .route_layer(middleware::from_fn_with_state(
-            app_state.clone(),
-            auth_middleware,
-        ))
-        .with_state(app_state);
-
-    // Combine routes
-    let app = Router::new()
-        .merge(public_routes)
-        .merge(protected_routes)
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
-        );
+    let repositories = Repositories::arcade(&db);
+    let app_state = AppState::new(config.clone(), db, auth, repositories, dependencies);
+
+    // Erase accounts whose deletion grace period has ended
+    erasure::spawn_purge_job(app_state.clone());
+    // Delete export archives past their download window
+    export::spawn_cleanup_job(app_state.clone());
+
+    let app = routes::router(app_state);
 
     // Run the server
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
+    let addr = config.server.addr();
     tracing::info!("Listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
+
+const USAGE: &str = "Usage:
+  cynnycty-backend                  run the server
+  cynnycty-backend migrate          apply pending migrations and exit
+  cynnycty-backend mint-token <subject> [--email EMAIL] [--name NAME] [--ttl SECONDS]
+                                    print a dev auth token (AUTH_PROVIDER=dev only)";
+
+enum Command {
+    Serve,
+    Migrate,
+    MintToken {
+        subject: String,
+        email: Option<String>,
+        name: Option<String>,
+        ttl: Duration,
+    },
+}
+
+fn parse_command(args: Vec<String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    match args.next().as_deref() {
+        None => Ok(Command::Serve),
+        Some("migrate") => match args.next() {
+            None => Ok(Command::Migrate),
+            Some(extra) => Err(format!("Unexpected argument '{}'", extra)),
+        },
+        Some("mint-token") => {
+            let subject = args.next().ok_or("mint-token needs a subject")?;
+            let (mut email, mut name, mut ttl) = (None, None, Duration::from_secs(60 * 60));
+            while let Some(flag) = args.next() {
+                let value = args.next().ok_or(format!("{} needs a value", flag))?;
+                match flag.as_str() {
+                    "--email" => email = Some(value),
+                    "--name" => name = Some(value),
+                    "--ttl" => {
+                        let seconds = value
+                            .parse::<u64>()
+                            .map_err(|_| format!("--ttl: '{}' is not a number of seconds", value))?;
+                        ttl = Duration::from_secs(seconds);
+                    }
+                    _ => return Err(format!("Unknown option '{}'", flag)),
+                }
+            }
+            Ok(Command::MintToken { subject, email, name, ttl })
+        }
+        Some(other) => Err(format!("Unknown command '{}'", other)),
+    }
+}
@@ -0,0 +1,81 @@
+//! Tri-state fields for partial updates
+//!
+//! JSON Merge Patch (RFC 7396) tells "leave this alone" (member absent) from
+//! "remove this" (member set to `null`), which `Option` can't. Request
+//! fields typed [`Patch`] need `#[serde(default)]` so an absent member
+//! deserializes to [`Patch::Absent`].
+
+use serde::{Deserialize, Deserializer};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Patch<T> {
+    /// Not mentioned: leave unchanged
+    #[default]
+    Absent,
+    /// Explicit `null`: clear
+    Null,
+    /// Set to this value
+    Value(T),
+}
+
+impl<T> Patch<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Patch::Absent)
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Patch<U> {
+        match self {
+            Patch::Absent => Patch::Absent,
+            Patch::Null => Patch::Null,
+            Patch::Value(value) => Patch::Value(f(value)),
+        }
+    }
+}
+
+/// `None` clears, as a full replacement does for a missing value
+impl<T> From<Option<T>> for Patch<T> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Patch::Null, Patch::Value)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patch<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::<T>::deserialize(deserializer).map(Patch::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Request {
+        #[serde(default)]
+        about_me: Patch<String>,
+    }
+
+    fn about_me(json: &str) -> Patch<String> {
+        serde_json::from_str::<Request>(json).unwrap().about_me
+    }
+
+    #[test]
+    fn absent_null_and_value() {
+        assert_eq!(about_me("{}"), Patch::Absent);
+        assert_eq!(about_me(r#"{"about_me": null}"#), Patch::Null);
+        assert_eq!(about_me(r#"{"about_me": "Hi"}"#), Patch::Value("Hi".to_string()));
+    }
+
+    #[test]
+    fn wrong_type_is_an_error() {
+        assert!(serde_json::from_str::<Request>(r#"{"about_me": 1}"#).is_err());
+    }
+
+    #[test]
+    fn options_replace() {
+        assert_eq!(Patch::from(None::<String>), Patch::Null);
+        assert_eq!(Patch::from(Some(1)), Patch::Value(1));
+        assert_eq!(Patch::Value(2).map(|n| n * 2), Patch::Value(4));
+        assert!(Patch::<u8>::Absent.map(|n| n * 2).is_absent());
+    }
+}
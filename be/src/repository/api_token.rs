@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::auth::scopes::{self, Scope};
+use crate::db::error::DbError;
+use crate::db::timestamp;
+
+/// A stored API token; never includes the plaintext or its hash
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    pub token_id: String,
+    pub user_id: String,
+    pub name: String,
+    /// Leading characters of the plaintext, for telling tokens apart
+    pub prefix: String,
+    #[serde(default, deserialize_with = "scopes::deserialize_scopes")]
+    pub scopes: Vec<Scope>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiToken {
+    /// Not revoked and not past its expiry
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// Fields required to create a token
+#[derive(Debug, Clone)]
+pub struct NewApiToken {
+    pub token_id: String,
+    pub user_id: String,
+    pub name: String,
+    pub token_hash: String,
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Storage for ApiToken records
+#[async_trait]
+pub trait ApiTokenRepository: Send + Sync {
+    async fn create(&self, token: NewApiToken) -> Result<ApiToken, DbError>;
+
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>, DbError>;
+
+    /// The user's tokens, newest first, including revoked ones
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<ApiToken>, DbError>;
+
+    /// Revoke one of the user's tokens; [`DbError::NotFound`] if the user
+    /// has no such token
+    async fn revoke(&self, user_id: &str, token_id: &str) -> Result<ApiToken, DbError>;
+
+    /// Record that the token was just used
+    async fn touch(&self, token_id: &str) -> Result<(), DbError>;
+
+    /// Remove all of the user's tokens, returning how many there were
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError>;
+}
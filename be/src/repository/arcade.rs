@@ -0,0 +1,610 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::auth::roles::Role;
+use crate::auth::scopes::Scope;
+use crate::db::connection::DatabaseConnection;
+use crate::db::error::DbError;
+use crate::db::query::Query;
+use crate::patch::Patch;
+use crate::repository::api_token::{ApiToken, ApiTokenRepository, NewApiToken};
+use crate::repository::erasure::{ErasureRecord, ErasureRepository, NewErasureRecord};
+use crate::repository::export::{ExportJob, ExportRepository, ExportStatus};
+use crate::repository::handle::{HandleHistory, HandleHistoryRepository, NewHandleHistory};
+use crate::repository::identity::{AuthIdentity, AuthIdentityRepository, NewAuthIdentity};
+use crate::repository::profile::{NewProfile, Profile, ProfileRepository, ProfileUpdate};
+
+/// Result row of UPDATE/DELETE commands
+#[derive(Deserialize)]
+struct CountRow {
+    count: i64,
+}
+
+/// Rows removed by a DELETE command
+async fn delete_count(db: &DatabaseConnection, query: &Query) -> Result<u64, DbError> {
+    let deleted = db.command::<CountRow>(query).await?.first().map_or(0, |row| row.count);
+    Ok(deleted.max(0) as u64)
+}
+
+/// Profile storage in ArcadeDB
+#[derive(Clone)]
+pub struct ArcadeProfileRepository {
+    db: DatabaseConnection,
+}
+
+impl ArcadeProfileRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    async fn find_one(&self, query: Query) -> Result<Option<Profile>, DbError> {
+        Ok(self.db.query::<Profile>(&query).await?.into_iter().next())
+    }
+
+    /// Run an UPDATE of one profile and return it as stored afterwards
+    async fn update_one(&self, query: Query, user_id: &str) -> Result<Profile, DbError> {
+        let updated = self
+            .db
+            .command::<CountRow>(&query)
+            .await?
+            .first()
+            .map_or(0, |row| row.count);
+
+        if updated == 0 {
+            return Err(DbError::NotFound(format!("profile {}", user_id)));
+        }
+
+        self.find_by_user_id(user_id)
+            .await?
+            .ok_or_else(|| DbError::NotFound(format!("profile {}", user_id)))
+    }
+}
+
+#[async_trait]
+impl ProfileRepository for ArcadeProfileRepository {
+    async fn find_by_user_id(&self, user_id: &str) -> Result<Option<Profile>, DbError> {
+        self.find_one(Query::new("SELECT FROM Profile WHERE userId = :userId").bind("userId", user_id))
+            .await
+    }
+
+    async fn find_by_clerk_id(&self, clerk_id: &str) -> Result<Option<Profile>, DbError> {
+        self.find_one(Query::new("SELECT FROM Profile WHERE clerkId = :clerkId").bind("clerkId", clerk_id))
+            .await
+    }
+
+    async fn find_by_handle(&self, handle: &str) -> Result<Option<Profile>, DbError> {
+        self.find_one(
+            Query::new("SELECT FROM Profile WHERE handleLower = :handleLower").bind("handleLower", handle.to_lowercase()),
+        )
+        .await
+    }
+
+    async fn create(&self, profile: NewProfile) -> Result<Profile, DbError> {
+        let query = Query::new(
+            "INSERT INTO Profile SET userId = :userId, clerkId = :clerkId, displayName = :displayName, createdAt = sysdate(), updatedAt = sysdate(), version = 1",
+        )
+        .bind("userId", profile.user_id.as_str())
+        .bind("clerkId", profile.clerk_id)
+        .bind("displayName", profile.display_name);
+
+        // INSERT returns the created record
+        match self.db.command::<Profile>(&query).await?.into_iter().next() {
+            Some(created) => Ok(created),
+            None => self
+                .find_by_user_id(&profile.user_id)
+                .await?
+                .ok_or_else(|| DbError::NotFound(format!("profile {}", profile.user_id))),
+        }
+    }
+
+    async fn update(
+        &self,
+        user_id: &str,
+        update: ProfileUpdate,
+        expected_version: Option<u64>,
+    ) -> Result<Profile, DbError> {
+        // Only the SET list is assembled here, from fixed property names;
+        // every value is bound as a parameter
+        let mut assignments = vec!["updatedAt = sysdate()", "version = version + 1"];
+        let mut query_params = Vec::new();
+
+        let fields = [
+            ("displayName", "displayName = :displayName", "displayName = null", update.display_name),
+            ("aboutMe", "aboutMe = :aboutMe", "aboutMe = null", update.about_me),
+            ("avatarUrl", "avatarUrl = :avatarUrl", "avatarUrl = null", update.avatar_url),
+        ];
+        for (name, set, clear, value) in fields {
+            match value {
+                Patch::Absent => {}
+                Patch::Null => assignments.push(clear),
+                Patch::Value(value) => {
+                    assignments.push(set);
+                    query_params.push((name, value));
+                }
+            }
+        }
+
+        let condition = match expected_version {
+            Some(_) => "userId = :userId AND version = :version",
+            None => "userId = :userId",
+        };
+        let mut query = Query::new(format!(
+            "UPDATE Profile SET {} WHERE {}",
+            assignments.join(", "),
+            condition
+        ))
+        .bind("userId", user_id);
+
+        if let Some(version) = expected_version {
+            query = query.bind("version", version);
+        }
+        for (name, value) in query_params {
+            query = query.bind(name, value);
+        }
+
+        let result = self.update_one(query, user_id).await;
+
+        // Nothing matched: either the profile is gone or another write moved
+        // it past the expected version
+        if let (Err(DbError::NotFound(_)), Some(version)) = (&result, expected_version)
+            && self.find_by_user_id(user_id).await?.is_some()
+        {
+            return Err(DbError::Conflict(format!(
+                "profile {} is no longer at version {}",
+                user_id, version
+            )));
+        }
+
+        result
+    }
+
+    async fn delete(&self, user_id: &str) -> Result<(), DbError> {
+        let query = Query::new("DELETE FROM Profile WHERE userId = :userId").bind("userId", user_id);
+
+        let deleted = self
+            .db
+            .command::<CountRow>(&query)
+            .await?
+            .first()
+            .map_or(0, |row| row.count);
+
+        if deleted == 0 {
+            return Err(DbError::NotFound(format!("profile {}", user_id)));
+        }
+
+        Ok(())
+    }
+
+    async fn tombstone(&self, user_id: &str) -> Result<Profile, DbError> {
+        let query = Query::new(
+            "UPDATE Profile SET deletedAt = sysdate(), updatedAt = sysdate(), version = version + 1, displayName = null, aboutMe = null, avatarUrl = null WHERE userId = :userId",
+        )
+        .bind("userId", user_id);
+
+        self.db.command::<CountRow>(&query).await?;
+
+        self.find_by_user_id(user_id)
+            .await?
+            .ok_or_else(|| DbError::NotFound(format!("profile {}", user_id)))
+    }
+
+    async fn set_handle(&self, user_id: &str, handle: &str) -> Result<Profile, DbError> {
+        let query = Query::new(
+            "UPDATE Profile SET handle = :handle, handleLower = :handleLower, handleChangedAt = sysdate(), updatedAt = sysdate(), version = version + 1 WHERE userId = :userId",
+        )
+        .bind("handle", handle)
+        .bind("handleLower", handle.to_lowercase())
+        .bind("userId", user_id);
+
+        self.update_one(query, user_id).await
+    }
+
+    async fn set_roles(&self, user_id: &str, roles: &[Role]) -> Result<Profile, DbError> {
+        let names: Vec<&str> = roles.iter().map(Role::as_str).collect();
+        let query = Query::new(
+            "UPDATE Profile SET roles = :roles, updatedAt = sysdate(), version = version + 1 WHERE userId = :userId",
+        )
+        .bind("roles", names)
+        .bind("userId", user_id);
+
+        self.update_one(query, user_id).await
+    }
+
+    async fn schedule_deletion(
+        &self,
+        user_id: &str,
+        requested_by: &str,
+        scheduled_for: DateTime<Utc>,
+    ) -> Result<Profile, DbError> {
+        let query = Query::new(
+            "UPDATE Profile SET deletionRequestedAt = sysdate(), deletionScheduledFor = :scheduledFor, deletionRequestedBy = :requestedBy, updatedAt = sysdate(), version = version + 1 WHERE userId = :userId",
+        )
+        .bind("scheduledFor", scheduled_for.timestamp_millis())
+        .bind("requestedBy", requested_by)
+        .bind("userId", user_id);
+
+        self.update_one(query, user_id).await
+    }
+
+    async fn cancel_deletion(&self, user_id: &str) -> Result<Profile, DbError> {
+        let query = Query::new(
+            "UPDATE Profile SET deletionRequestedAt = null, deletionScheduledFor = null, deletionRequestedBy = null, updatedAt = sysdate(), version = version + 1 WHERE userId = :userId",
+        )
+        .bind("userId", user_id);
+
+        self.update_one(query, user_id).await
+    }
+
+    async fn due_for_deletion(&self, now: DateTime<Utc>) -> Result<Vec<Profile>, DbError> {
+        let query = Query::new("SELECT FROM Profile WHERE deletionScheduledFor <= :now")
+            .bind("now", now.timestamp_millis());
+        self.db.query::<Profile>(&query).await
+    }
+}
+
+/// API token storage in ArcadeDB
+#[derive(Clone)]
+pub struct ArcadeApiTokenRepository {
+    db: DatabaseConnection,
+}
+
+impl ArcadeApiTokenRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    async fn find_by_id(&self, token_id: &str) -> Result<Option<ApiToken>, DbError> {
+        let query = Query::new("SELECT FROM ApiToken WHERE tokenId = :tokenId").bind("tokenId", token_id);
+        Ok(self.db.query::<ApiToken>(&query).await?.into_iter().next())
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepository for ArcadeApiTokenRepository {
+    async fn create(&self, token: NewApiToken) -> Result<ApiToken, DbError> {
+        let scopes: Vec<&str> = token.scopes.iter().map(Scope::as_str).collect();
+        let query = Query::new(
+            "INSERT INTO ApiToken SET tokenId = :tokenId, userId = :userId, name = :name, tokenHash = :tokenHash, prefix = :prefix, scopes = :scopes, expiresAt = :expiresAt, createdAt = sysdate()",
+        )
+        .bind("tokenId", token.token_id.as_str())
+        .bind("userId", token.user_id)
+        .bind("name", token.name)
+        .bind("tokenHash", token.token_hash)
+        .bind("prefix", token.prefix)
+        .bind("scopes", scopes)
+        .bind("expiresAt", token.expires_at.map(|at| at.timestamp_millis()));
+
+        match self.db.command::<ApiToken>(&query).await?.into_iter().next() {
+            Some(created) => Ok(created),
+            None => self
+                .find_by_id(&token.token_id)
+                .await?
+                .ok_or_else(|| DbError::NotFound(format!("API token {}", token.token_id))),
+        }
+    }
+
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>, DbError> {
+        let query = Query::new("SELECT FROM ApiToken WHERE tokenHash = :tokenHash").bind("tokenHash", token_hash);
+        Ok(self.db.query::<ApiToken>(&query).await?.into_iter().next())
+    }
+
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<ApiToken>, DbError> {
+        let query = Query::new("SELECT FROM ApiToken WHERE userId = :userId ORDER BY createdAt DESC")
+            .bind("userId", user_id);
+        self.db.query::<ApiToken>(&query).await
+    }
+
+    async fn revoke(&self, user_id: &str, token_id: &str) -> Result<ApiToken, DbError> {
+        // Keep the first revocation time if revoked twice
+        let query = Query::new(
+            "UPDATE ApiToken SET revokedAt = sysdate() WHERE tokenId = :tokenId AND userId = :userId AND revokedAt IS NULL",
+        )
+        .bind("tokenId", token_id)
+        .bind("userId", user_id);
+
+        self.db.command::<CountRow>(&query).await?;
+
+        self.find_by_id(token_id)
+            .await?
+            .filter(|token| token.user_id == user_id)
+            .ok_or_else(|| DbError::NotFound(format!("API token {}", token_id)))
+    }
+
+    async fn touch(&self, token_id: &str) -> Result<(), DbError> {
+        let query = Query::new("UPDATE ApiToken SET lastUsedAt = sysdate() WHERE tokenId = :tokenId")
+            .bind("tokenId", token_id);
+        self.db.command::<CountRow>(&query).await?;
+        Ok(())
+    }
+
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError> {
+        let query = Query::new("DELETE FROM ApiToken WHERE userId = :userId").bind("userId", user_id);
+        delete_count(&self.db, &query).await
+    }
+}
+
+/// Identity storage in ArcadeDB
+#[derive(Clone)]
+pub struct ArcadeAuthIdentityRepository {
+    db: DatabaseConnection,
+}
+
+impl ArcadeAuthIdentityRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AuthIdentityRepository for ArcadeAuthIdentityRepository {
+    async fn find(&self, provider: &str, subject: &str) -> Result<Option<AuthIdentity>, DbError> {
+        let query = Query::new("SELECT FROM AuthIdentity WHERE provider = :provider AND subject = :subject")
+            .bind("provider", provider)
+            .bind("subject", subject);
+        Ok(self.db.query::<AuthIdentity>(&query).await?.into_iter().next())
+    }
+
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<AuthIdentity>, DbError> {
+        let query = Query::new("SELECT FROM AuthIdentity WHERE userId = :userId ORDER BY linkedAt ASC")
+            .bind("userId", user_id);
+        self.db.query::<AuthIdentity>(&query).await
+    }
+
+    async fn create(&self, identity: NewAuthIdentity) -> Result<AuthIdentity, DbError> {
+        let query = Query::new(
+            "INSERT INTO AuthIdentity SET provider = :provider, subject = :subject, userId = :userId, linkedAt = sysdate()",
+        )
+        .bind("provider", identity.provider.as_str())
+        .bind("subject", identity.subject.as_str())
+        .bind("userId", identity.user_id);
+
+        match self.db.command::<AuthIdentity>(&query).await?.into_iter().next() {
+            Some(created) => Ok(created),
+            None => self
+                .find(&identity.provider, &identity.subject)
+                .await?
+                .ok_or_else(|| DbError::NotFound(format!("{} identity {}", identity.provider, identity.subject))),
+        }
+    }
+
+    async fn delete(&self, user_id: &str, provider: &str, subject: &str) -> Result<(), DbError> {
+        let query = Query::new(
+            "DELETE FROM AuthIdentity WHERE userId = :userId AND provider = :provider AND subject = :subject",
+        )
+        .bind("userId", user_id)
+        .bind("provider", provider)
+        .bind("subject", subject);
+
+        let deleted = self
+            .db
+            .command::<CountRow>(&query)
+            .await?
+            .first()
+            .map_or(0, |row| row.count);
+
+        if deleted == 0 {
+            return Err(DbError::NotFound(format!("{} identity {}", provider, subject)));
+        }
+
+        Ok(())
+    }
+
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError> {
+        let query = Query::new("DELETE FROM AuthIdentity WHERE userId = :userId").bind("userId", user_id);
+        delete_count(&self.db, &query).await
+    }
+}
+
+/// Erasure records in ArcadeDB
+#[derive(Clone)]
+pub struct ArcadeErasureRepository {
+    db: DatabaseConnection,
+}
+
+impl ArcadeErasureRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ErasureRepository for ArcadeErasureRepository {
+    async fn create(&self, record: NewErasureRecord) -> Result<ErasureRecord, DbError> {
+        let query = Query::new(
+            "INSERT INTO ErasureRecord SET userId = :userId, subjectHashes = :subjectHashes, requestedBy = :requestedBy, requestedAt = :requestedAt, erasedAt = sysdate()",
+        )
+        .bind("userId", record.user_id.as_str())
+        .bind("subjectHashes", record.subject_hashes)
+        .bind("requestedBy", record.requested_by)
+        .bind("requestedAt", record.requested_at.map(|at| at.timestamp_millis()));
+
+        match self.db.command::<ErasureRecord>(&query).await?.into_iter().next() {
+            Some(created) => Ok(created),
+            None => {
+                let query = Query::new("SELECT FROM ErasureRecord WHERE userId = :userId")
+                    .bind("userId", record.user_id.as_str());
+                self.db
+                    .query::<ErasureRecord>(&query)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| DbError::NotFound(format!("erasure record {}", record.user_id)))
+            }
+        }
+    }
+
+    async fn is_erased(&self, subject_hash: &str) -> Result<bool, DbError> {
+        // Only consulted for identities without a profile, i.e. new sign-ups
+        let query = Query::new("SELECT userId FROM ErasureRecord WHERE :subjectHash IN subjectHashes LIMIT 1")
+            .bind("subjectHash", subject_hash);
+        Ok(!self.db.query::<serde_json::Value>(&query).await?.is_empty())
+    }
+}
+
+/// Export jobs in ArcadeDB
+#[derive(Clone)]
+pub struct ArcadeExportRepository {
+    db: DatabaseConnection,
+}
+
+/// Every ExportJob property except the archive, which status polling doesn't need
+const EXPORT_JOB_FIELDS: &str = "exportId, userId, status, requestedAt, completedAt, expiresAt, error";
+
+impl ArcadeExportRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    async fn find_by_id(&self, export_id: &str) -> Result<Option<ExportJob>, DbError> {
+        let query = Query::new(format!("SELECT {} FROM ExportJob WHERE exportId = :exportId", EXPORT_JOB_FIELDS))
+            .bind("exportId", export_id);
+        Ok(self.db.query::<ExportJob>(&query).await?.into_iter().next())
+    }
+}
+
+#[async_trait]
+impl ExportRepository for ArcadeExportRepository {
+    async fn create(&self, export_id: &str, user_id: &str) -> Result<ExportJob, DbError> {
+        let query = Query::new(
+            "INSERT INTO ExportJob SET exportId = :exportId, userId = :userId, status = :status, requestedAt = sysdate()",
+        )
+        .bind("exportId", export_id)
+        .bind("userId", user_id)
+        .bind("status", ExportStatus::Pending.as_str());
+
+        match self.db.command::<ExportJob>(&query).await?.into_iter().next() {
+            Some(created) => Ok(created),
+            None => self
+                .find_by_id(export_id)
+                .await?
+                .ok_or_else(|| DbError::NotFound(format!("export {}", export_id))),
+        }
+    }
+
+    async fn find(&self, user_id: &str, export_id: &str) -> Result<Option<ExportJob>, DbError> {
+        Ok(self.find_by_id(export_id).await?.filter(|job| job.user_id == user_id))
+    }
+
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<ExportJob>, DbError> {
+        let query = Query::new(format!(
+            "SELECT {} FROM ExportJob WHERE userId = :userId ORDER BY requestedAt DESC",
+            EXPORT_JOB_FIELDS
+        ))
+        .bind("userId", user_id);
+        self.db.query::<ExportJob>(&query).await
+    }
+
+    async fn mark_running(&self, export_id: &str) -> Result<(), DbError> {
+        let query = Query::new("UPDATE ExportJob SET status = :status WHERE exportId = :exportId")
+            .bind("status", ExportStatus::Running.as_str())
+            .bind("exportId", export_id);
+        self.db.command::<CountRow>(&query).await?;
+        Ok(())
+    }
+
+    async fn complete(&self, export_id: &str, archive: String, expires_at: DateTime<Utc>) -> Result<(), DbError> {
+        let query = Query::new(
+            "UPDATE ExportJob SET status = :status, archive = :archive, completedAt = sysdate(), expiresAt = :expiresAt WHERE exportId = :exportId",
+        )
+        .bind("status", ExportStatus::Ready.as_str())
+        .bind("archive", archive)
+        .bind("expiresAt", expires_at.timestamp_millis())
+        .bind("exportId", export_id);
+        self.db.command::<CountRow>(&query).await?;
+        Ok(())
+    }
+
+    async fn fail(&self, export_id: &str, error: &str) -> Result<(), DbError> {
+        let query = Query::new(
+            "UPDATE ExportJob SET status = :status, error = :error, completedAt = sysdate() WHERE exportId = :exportId",
+        )
+        .bind("status", ExportStatus::Failed.as_str())
+        .bind("error", error)
+        .bind("exportId", export_id);
+        self.db.command::<CountRow>(&query).await?;
+        Ok(())
+    }
+
+    async fn archive(&self, export_id: &str) -> Result<Option<String>, DbError> {
+        #[derive(Deserialize)]
+        struct ArchiveRow {
+            #[serde(default)]
+            archive: Option<String>,
+        }
+
+        let query = Query::new("SELECT archive FROM ExportJob WHERE exportId = :exportId AND status = :status")
+            .bind("exportId", export_id)
+            .bind("status", ExportStatus::Ready.as_str());
+        Ok(self
+            .db
+            .query::<ArchiveRow>(&query)
+            .await?
+            .into_iter()
+            .next()
+            .and_then(|row| row.archive))
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64, DbError> {
+        let query = Query::new("DELETE FROM ExportJob WHERE expiresAt <= :now").bind("now", now.timestamp_millis());
+        delete_count(&self.db, &query).await
+    }
+
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError> {
+        let query = Query::new("DELETE FROM ExportJob WHERE userId = :userId").bind("userId", user_id);
+        delete_count(&self.db, &query).await
+    }
+}
+
+/// Released handles in ArcadeDB
+#[derive(Clone)]
+pub struct ArcadeHandleHistoryRepository {
+    db: DatabaseConnection,
+}
+
+impl ArcadeHandleHistoryRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl HandleHistoryRepository for ArcadeHandleHistoryRepository {
+    async fn record(&self, entry: NewHandleHistory) -> Result<HandleHistory, DbError> {
+        let query = Query::new(
+            "INSERT INTO HandleHistory SET handle = :handle, handleLower = :handleLower, userId = :userId, releasedAt = sysdate(), expiresAt = :expiresAt",
+        )
+        .bind("handle", entry.handle.as_str())
+        .bind("handleLower", entry.handle.to_lowercase())
+        .bind("userId", entry.user_id.as_str())
+        .bind("expiresAt", entry.expires_at.timestamp_millis());
+
+        match self.db.command::<HandleHistory>(&query).await?.into_iter().next() {
+            Some(created) => Ok(created),
+            None => self
+                .find_active(&entry.handle, Utc::now())
+                .await?
+                .ok_or_else(|| DbError::NotFound(format!("handle history {}", entry.handle))),
+        }
+    }
+
+    async fn find_active(&self, handle: &str, now: DateTime<Utc>) -> Result<Option<HandleHistory>, DbError> {
+        let query = Query::new(
+            "SELECT FROM HandleHistory WHERE handleLower = :handleLower AND expiresAt > :now ORDER BY releasedAt DESC LIMIT 1",
+        )
+        .bind("handleLower", handle.to_lowercase())
+        .bind("now", now.timestamp_millis());
+        Ok(self.db.query::<HandleHistory>(&query).await?.into_iter().next())
+    }
+
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<HandleHistory>, DbError> {
+        let query = Query::new("SELECT FROM HandleHistory WHERE userId = :userId ORDER BY releasedAt DESC")
+            .bind("userId", user_id);
+        self.db.query::<HandleHistory>(&query).await
+    }
+
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError> {
+        let query = Query::new("DELETE FROM HandleHistory WHERE userId = :userId").bind("userId", user_id);
+        delete_count(&self.db, &query).await
+    }
+}
@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::db::error::DbError;
+use crate::db::timestamp;
+
+/// Compliance record of an erased account; holds no personal data
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErasureRecord {
+    pub user_id: String,
+    /// [`subject_hash`] of every identity the account had
+    #[serde(default)]
+    pub subject_hashes: Vec<String>,
+    #[serde(default)]
+    pub requested_by: Option<String>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub requested_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub erased_at: Option<DateTime<Utc>>,
+}
+
+/// Fields required to record an erasure
+#[derive(Debug, Clone)]
+pub struct NewErasureRecord {
+    pub user_id: String,
+    pub subject_hashes: Vec<String>,
+    pub requested_by: Option<String>,
+    pub requested_at: Option<DateTime<Utc>>,
+}
+
+/// Hex SHA-256 identifying an identity without storing its subject
+pub fn subject_hash(provider: &str, subject: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(provider.as_bytes());
+    hasher.update([0]);
+    hasher.update(subject.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Storage for ErasureRecord records
+///
+/// Implementations report a second record for the same userId as
+/// [`DbError::Conflict`].
+#[async_trait]
+pub trait ErasureRepository: Send + Sync {
+    async fn create(&self, record: NewErasureRecord) -> Result<ErasureRecord, DbError>;
+
+    /// Whether an identity with this [`subject_hash`] belonged to an erased
+    /// account
+    async fn is_erased(&self, subject_hash: &str) -> Result<bool, DbError>;
+}
@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::db::error::DbError;
+use crate::db::timestamp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportStatus {
+    Pending,
+    Running,
+    Ready,
+    Failed,
+}
+
+impl ExportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportStatus::Pending => "pending",
+            ExportStatus::Running => "running",
+            ExportStatus::Ready => "ready",
+            ExportStatus::Failed => "failed",
+        }
+    }
+
+    /// Not finished yet
+    pub fn is_in_progress(&self) -> bool {
+        matches!(self, ExportStatus::Pending | ExportStatus::Running)
+    }
+}
+
+/// A stored export job, without its archive
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportJob {
+    pub export_id: String,
+    pub user_id: String,
+    pub status: ExportStatus,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub requested_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// When the archive is deleted; set once ready
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Storage for ExportJob records
+#[async_trait]
+pub trait ExportRepository: Send + Sync {
+    /// Record a new pending job
+    async fn create(&self, export_id: &str, user_id: &str) -> Result<ExportJob, DbError>;
+
+    /// One of the user's jobs
+    async fn find(&self, user_id: &str, export_id: &str) -> Result<Option<ExportJob>, DbError>;
+
+    /// The user's jobs, newest first
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<ExportJob>, DbError>;
+
+    async fn mark_running(&self, export_id: &str) -> Result<(), DbError>;
+
+    /// Store the finished archive, kept until `expires_at`
+    async fn complete(&self, export_id: &str, archive: String, expires_at: DateTime<Utc>) -> Result<(), DbError>;
+
+    async fn fail(&self, export_id: &str, error: &str) -> Result<(), DbError>;
+
+    /// The archive of a ready job
+    async fn archive(&self, export_id: &str) -> Result<Option<String>, DbError>;
+
+    /// Remove jobs whose archive expired at or before `now`, returning how
+    /// many there were
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64, DbError>;
+
+    /// Remove all of the user's jobs, returning how many there were
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError>;
+}
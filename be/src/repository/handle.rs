@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::db::error::DbError;
+use crate::db::timestamp;
+
+/// A handle a user gave up, redirecting to them until `expires_at`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandleHistory {
+    pub handle: String,
+    pub user_id: String,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub released_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Fields required to record a released handle
+#[derive(Debug, Clone)]
+pub struct NewHandleHistory {
+    pub handle: String,
+    pub user_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Storage for HandleHistory records
+#[async_trait]
+pub trait HandleHistoryRepository: Send + Sync {
+    async fn record(&self, entry: NewHandleHistory) -> Result<HandleHistory, DbError>;
+
+    /// The latest release of this handle, ignoring case, that hasn't expired
+    /// by `now`
+    async fn find_active(&self, handle: &str, now: DateTime<Utc>) -> Result<Option<HandleHistory>, DbError>;
+
+    /// The user's released handles, newest first
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<HandleHistory>, DbError>;
+
+    /// Remove all of the user's released handles, returning how many there
+    /// were
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError>;
+}
@@ -0,0 +1,203 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::auth::clerk;
+use crate::db::error::DbError;
+use crate::db::timestamp;
+use crate::repository::profile::{NewProfile, Profile, ProfileRepository};
+
+/// An external identity (provider + subject) linked to a profile
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthIdentity {
+    pub provider: String,
+    pub subject: String,
+    pub user_id: String,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub linked_at: Option<DateTime<Utc>>,
+}
+
+/// Fields required to link an identity
+#[derive(Debug, Clone)]
+pub struct NewAuthIdentity {
+    pub provider: String,
+    pub subject: String,
+    pub user_id: String,
+}
+
+/// Storage for AuthIdentity records
+///
+/// Implementations report an identity already linked to any profile as
+/// [`DbError::Conflict`] and a missing identity on delete as
+/// [`DbError::NotFound`].
+#[async_trait]
+pub trait AuthIdentityRepository: Send + Sync {
+    async fn find(&self, provider: &str, subject: &str) -> Result<Option<AuthIdentity>, DbError>;
+
+    /// The profile's identities, oldest link first
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<AuthIdentity>, DbError>;
+
+    async fn create(&self, identity: NewAuthIdentity) -> Result<AuthIdentity, DbError>;
+
+    /// Remove one of the profile's identities
+    async fn delete(&self, user_id: &str, provider: &str, subject: &str) -> Result<(), DbError>;
+
+    /// Remove all of the profile's identities, returning how many there were
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError>;
+}
+
+/// Find the profile an identity signs in to, without creating anything
+///
+/// Profiles created before identities were tracked are found by `clerkId`,
+/// for Clerk identities only.
+pub async fn find_by_identity(
+    profiles: &dyn ProfileRepository,
+    identities: &dyn AuthIdentityRepository,
+    provider: &str,
+    subject: &str,
+) -> Result<Option<Profile>, DbError> {
+    match identities.find(provider, subject).await? {
+        Some(identity) => profiles.find_by_user_id(&identity.user_id).await,
+        None => find_legacy(profiles, provider, subject).await,
+    }
+}
+
+/// A profile from before identities were tracked, keyed by its Clerk user ID
+///
+/// Other providers never match: their subjects are unrelated to Clerk's, and
+/// a token whose `sub` happened to equal a Clerk user ID must not sign in to
+/// that user's profile.
+async fn find_legacy(
+    profiles: &dyn ProfileRepository,
+    provider: &str,
+    subject: &str,
+) -> Result<Option<Profile>, DbError> {
+    if provider != clerk::PROVIDER_NAME {
+        return Ok(None);
+    }
+    profiles.find_by_clerk_id(subject).await
+}
+
+/// Find the profile for an identity, creating profile and identity if there
+/// is none
+///
+/// Safe to call concurrently for the same identity: every caller creates a
+/// profile and links the identity optimistically, the unique
+/// (provider, subject) index lets exactly one link win, and the losers
+/// delete their unlinked profile and re-read the winner's. A legacy profile
+/// found by `clerkId` (Clerk identities only) is linked the same way. All callers get the persisted
+/// profile, deleted or not.
+pub async fn find_or_create_by_identity(
+    profiles: &dyn ProfileRepository,
+    identities: &dyn AuthIdentityRepository,
+    provider: &str,
+    subject: &str,
+    display_name: impl FnOnce() -> String,
+) -> Result<Profile, DbError> {
+    if let Some(identity) = identities.find(provider, subject).await? {
+        return linked_profile(profiles, &identity).await;
+    }
+
+    let (profile, created) = match find_legacy(profiles, provider, subject).await? {
+        Some(legacy) => {
+            tracing::info!("Linking {} identity to existing profile: userId={}", provider, legacy.user_id);
+            (legacy, false)
+        }
+        None => {
+            let user_id = uuid::Uuid::new_v4().to_string();
+            tracing::info!("Creating new profile: userId={}, {} subject={}", user_id, provider, subject);
+            let profile = profiles
+                .create(NewProfile {
+                    user_id,
+                    clerk_id: None,
+                    display_name: Some(display_name()),
+                })
+                .await?;
+            (profile, true)
+        }
+    };
+
+    let linked = identities
+        .create(NewAuthIdentity {
+            provider: provider.to_string(),
+            subject: subject.to_string(),
+            user_id: profile.user_id.clone(),
+        })
+        .await;
+
+    match linked {
+        Ok(_) => Ok(profile),
+        Err(DbError::Conflict(message)) => {
+            // Lost the race to a concurrent request for the same identity
+            tracing::debug!("Concurrent link of {} subject {}: {}", provider, subject, message);
+            if created && let Err(e) = profiles.delete(&profile.user_id).await {
+                tracing::warn!("Failed to remove orphaned profile {}: {}", profile.user_id, e);
+            }
+            let identity = identities
+                .find(provider, subject)
+                .await?
+                .ok_or(DbError::Conflict(message))?;
+            linked_profile(profiles, &identity).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn linked_profile(profiles: &dyn ProfileRepository, identity: &AuthIdentity) -> Result<Profile, DbError> {
+    profiles
+        .find_by_user_id(&identity.user_id)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("profile {} of {} identity", identity.user_id, identity.provider)))
+}
+
+/// Why an identity could not be unlinked
+#[derive(Debug, thiserror::Error)]
+pub enum UnlinkError {
+    #[error("identity is not linked to this profile")]
+    NotFound,
+    #[error("a profile must keep at least one identity")]
+    LastIdentity,
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
+
+/// Unlink an identity from a profile, keeping at least one linked
+///
+/// Two concurrent unlinks could each see the other identity still present;
+/// if none remain afterwards the removed identity is linked again and
+/// [`UnlinkError::LastIdentity`] returned.
+pub async fn unlink_identity(
+    identities: &dyn AuthIdentityRepository,
+    user_id: &str,
+    provider: &str,
+    subject: &str,
+) -> Result<(), UnlinkError> {
+    let linked = identities.list_for_user(user_id).await?;
+    if !linked.iter().any(|identity| identity.provider == provider && identity.subject == subject) {
+        return Err(UnlinkError::NotFound);
+    }
+    if linked.len() < 2 {
+        return Err(UnlinkError::LastIdentity);
+    }
+
+    match identities.delete(user_id, provider, subject).await {
+        Ok(()) => {}
+        Err(DbError::NotFound(_)) => return Err(UnlinkError::NotFound),
+        Err(e) => return Err(e.into()),
+    }
+
+    if identities.list_for_user(user_id).await?.is_empty() {
+        tracing::warn!("Concurrent unlinks left userId={} without identities; restoring", user_id);
+        identities
+            .create(NewAuthIdentity {
+                provider: provider.to_string(),
+                subject: subject.to_string(),
+                user_id: user_id.to_string(),
+            })
+            .await?;
+        return Err(UnlinkError::LastIdentity);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,658 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::auth::roles::Role;
+use crate::db::error::DbError;
+use crate::repository::api_token::{ApiToken, ApiTokenRepository, NewApiToken};
+use crate::repository::erasure::{ErasureRecord, ErasureRepository, NewErasureRecord};
+use crate::repository::export::{ExportJob, ExportRepository, ExportStatus};
+use crate::repository::handle::{HandleHistory, HandleHistoryRepository, NewHandleHistory};
+use crate::repository::identity::{AuthIdentity, AuthIdentityRepository, NewAuthIdentity};
+use crate::patch::Patch;
+use crate::repository::profile::{NewProfile, Profile, ProfileRepository, ProfileUpdate};
+
+/// Profile storage in a process-local map, for tests
+///
+/// Enforces the same uniqueness rules as the ArcadeDB indexes on userId and
+/// clerkId.
+#[derive(Default)]
+pub struct InMemoryProfileRepository {
+    profiles: RwLock<HashMap<String, Profile>>,
+}
+
+impl InMemoryProfileRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every stored profile, for assertions
+    pub fn all(&self) -> Vec<Profile> {
+        self.profiles.read().unwrap().values().cloned().collect()
+    }
+}
+
+#[async_trait]
+impl ProfileRepository for InMemoryProfileRepository {
+    async fn find_by_user_id(&self, user_id: &str) -> Result<Option<Profile>, DbError> {
+        Ok(self.profiles.read().unwrap().get(user_id).cloned())
+    }
+
+    async fn find_by_clerk_id(&self, clerk_id: &str) -> Result<Option<Profile>, DbError> {
+        Ok(self
+            .profiles
+            .read()
+            .unwrap()
+            .values()
+            .find(|profile| profile.clerk_id.as_deref() == Some(clerk_id))
+            .cloned())
+    }
+
+    async fn find_by_handle(&self, handle: &str) -> Result<Option<Profile>, DbError> {
+        let handle = handle.to_lowercase();
+        Ok(self
+            .profiles
+            .read()
+            .unwrap()
+            .values()
+            .find(|profile| profile.handle.as_ref().is_some_and(|h| h.to_lowercase() == handle))
+            .cloned())
+    }
+
+    async fn create(&self, profile: NewProfile) -> Result<Profile, DbError> {
+        let mut profiles = self.profiles.write().unwrap();
+
+        if profiles.contains_key(&profile.user_id) {
+            return Err(DbError::Conflict(format!(
+                "duplicate key in index 'Profile_userId_idx': {}",
+                profile.user_id
+            )));
+        }
+
+        if let Some(clerk_id) = &profile.clerk_id
+            && profiles
+                .values()
+                .any(|existing| existing.clerk_id.as_ref() == Some(clerk_id))
+        {
+            return Err(DbError::Conflict(format!(
+                "duplicate key in index 'Profile_clerkId_idx': {}",
+                clerk_id
+            )));
+        }
+
+        let now = Utc::now();
+        let created = Profile {
+            user_id: profile.user_id,
+            clerk_id: profile.clerk_id,
+            handle: None,
+            handle_changed_at: None,
+            display_name: profile.display_name,
+            about_me: None,
+            avatar_url: None,
+            created_at: Some(now),
+            updated_at: Some(now),
+            roles: Vec::new(),
+            deleted_at: None,
+            deletion_requested_at: None,
+            deletion_scheduled_for: None,
+            deletion_requested_by: None,
+            version: 1,
+        };
+
+        profiles.insert(created.user_id.clone(), created.clone());
+        Ok(created)
+    }
+
+    async fn update(
+        &self,
+        user_id: &str,
+        update: ProfileUpdate,
+        expected_version: Option<u64>,
+    ) -> Result<Profile, DbError> {
+        let mut profiles = self.profiles.write().unwrap();
+        let profile = profiles
+            .get_mut(user_id)
+            .ok_or_else(|| DbError::NotFound(format!("profile {}", user_id)))?;
+
+        if let Some(version) = expected_version
+            && profile.version != version
+        {
+            return Err(DbError::Conflict(format!(
+                "profile {} is no longer at version {}",
+                user_id, version
+            )));
+        }
+
+        for (field, value) in [
+            (&mut profile.display_name, update.display_name),
+            (&mut profile.about_me, update.about_me),
+            (&mut profile.avatar_url, update.avatar_url),
+        ] {
+            match value {
+                Patch::Absent => {}
+                Patch::Null => *field = None,
+                Patch::Value(value) => *field = Some(value),
+            }
+        }
+        profile.updated_at = Some(Utc::now());
+        profile.version += 1;
+
+        Ok(profile.clone())
+    }
+
+    async fn delete(&self, user_id: &str) -> Result<(), DbError> {
+        self.profiles
+            .write()
+            .unwrap()
+            .remove(user_id)
+            .map(|_| ())
+            .ok_or_else(|| DbError::NotFound(format!("profile {}", user_id)))
+    }
+
+    async fn tombstone(&self, user_id: &str) -> Result<Profile, DbError> {
+        let mut profiles = self.profiles.write().unwrap();
+        let profile = profiles
+            .get_mut(user_id)
+            .ok_or_else(|| DbError::NotFound(format!("profile {}", user_id)))?;
+
+        let now = Utc::now();
+        profile.display_name = None;
+        profile.about_me = None;
+        profile.avatar_url = None;
+        profile.deleted_at = Some(now);
+        profile.updated_at = Some(now);
+        profile.version += 1;
+
+        Ok(profile.clone())
+    }
+
+    async fn set_handle(&self, user_id: &str, handle: &str) -> Result<Profile, DbError> {
+        let mut profiles = self.profiles.write().unwrap();
+
+        let handle_lower = handle.to_lowercase();
+        if profiles.values().any(|other| {
+            other.user_id != user_id && other.handle.as_ref().is_some_and(|h| h.to_lowercase() == handle_lower)
+        }) {
+            return Err(DbError::Conflict(format!(
+                "duplicate key in index 'Profile_handleLower_idx': {}",
+                handle_lower
+            )));
+        }
+
+        let profile = profiles
+            .get_mut(user_id)
+            .ok_or_else(|| DbError::NotFound(format!("profile {}", user_id)))?;
+
+        let now = Utc::now();
+        profile.handle = Some(handle.to_string());
+        profile.handle_changed_at = Some(now);
+        profile.updated_at = Some(now);
+        profile.version += 1;
+
+        Ok(profile.clone())
+    }
+
+    async fn set_roles(&self, user_id: &str, roles: &[Role]) -> Result<Profile, DbError> {
+        let mut profiles = self.profiles.write().unwrap();
+        let profile = profiles
+            .get_mut(user_id)
+            .ok_or_else(|| DbError::NotFound(format!("profile {}", user_id)))?;
+
+        profile.roles = roles.to_vec();
+        profile.updated_at = Some(Utc::now());
+        profile.version += 1;
+
+        Ok(profile.clone())
+    }
+
+    async fn schedule_deletion(
+        &self,
+        user_id: &str,
+        requested_by: &str,
+        scheduled_for: DateTime<Utc>,
+    ) -> Result<Profile, DbError> {
+        let mut profiles = self.profiles.write().unwrap();
+        let profile = profiles
+            .get_mut(user_id)
+            .ok_or_else(|| DbError::NotFound(format!("profile {}", user_id)))?;
+
+        let now = Utc::now();
+        profile.deletion_requested_at = Some(now);
+        profile.deletion_scheduled_for = Some(scheduled_for);
+        profile.deletion_requested_by = Some(requested_by.to_string());
+        profile.updated_at = Some(now);
+        profile.version += 1;
+
+        Ok(profile.clone())
+    }
+
+    async fn cancel_deletion(&self, user_id: &str) -> Result<Profile, DbError> {
+        let mut profiles = self.profiles.write().unwrap();
+        let profile = profiles
+            .get_mut(user_id)
+            .ok_or_else(|| DbError::NotFound(format!("profile {}", user_id)))?;
+
+        profile.deletion_requested_at = None;
+        profile.deletion_scheduled_for = None;
+        profile.deletion_requested_by = None;
+        profile.updated_at = Some(Utc::now());
+        profile.version += 1;
+
+        Ok(profile.clone())
+    }
+
+    async fn due_for_deletion(&self, now: DateTime<Utc>) -> Result<Vec<Profile>, DbError> {
+        Ok(self
+            .profiles
+            .read()
+            .unwrap()
+            .values()
+            .filter(|profile| profile.deletion_scheduled_for.is_some_and(|at| at <= now))
+            .cloned()
+            .collect())
+    }
+}
+
+/// API token storage in a process-local map, for tests
+#[derive(Default)]
+pub struct InMemoryApiTokenRepository {
+    /// Tokens with their hashes, keyed by tokenId
+    tokens: RwLock<HashMap<String, (String, ApiToken)>>,
+}
+
+impl InMemoryApiTokenRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepository for InMemoryApiTokenRepository {
+    async fn create(&self, token: NewApiToken) -> Result<ApiToken, DbError> {
+        let mut tokens = self.tokens.write().unwrap();
+
+        if tokens.contains_key(&token.token_id) {
+            return Err(DbError::Conflict(format!(
+                "duplicate key in index 'ApiToken_tokenId_idx': {}",
+                token.token_id
+            )));
+        }
+        if tokens.values().any(|(hash, _)| *hash == token.token_hash) {
+            return Err(DbError::Conflict(
+                "duplicate key in index 'ApiToken_tokenHash_idx'".to_string(),
+            ));
+        }
+
+        let created = ApiToken {
+            token_id: token.token_id,
+            user_id: token.user_id,
+            name: token.name,
+            prefix: token.prefix,
+            scopes: token.scopes,
+            created_at: Some(Utc::now()),
+            expires_at: token.expires_at,
+            last_used_at: None,
+            revoked_at: None,
+        };
+
+        tokens.insert(created.token_id.clone(), (token.token_hash, created.clone()));
+        Ok(created)
+    }
+
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>, DbError> {
+        Ok(self
+            .tokens
+            .read()
+            .unwrap()
+            .values()
+            .find(|(hash, _)| hash == token_hash)
+            .map(|(_, token)| token.clone()))
+    }
+
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<ApiToken>, DbError> {
+        let mut tokens: Vec<ApiToken> = self
+            .tokens
+            .read()
+            .unwrap()
+            .values()
+            .filter(|(_, token)| token.user_id == user_id)
+            .map(|(_, token)| token.clone())
+            .collect();
+        tokens.sort_by_key(|token| std::cmp::Reverse(token.created_at));
+        Ok(tokens)
+    }
+
+    async fn revoke(&self, user_id: &str, token_id: &str) -> Result<ApiToken, DbError> {
+        let mut tokens = self.tokens.write().unwrap();
+        let token = tokens
+            .get_mut(token_id)
+            .map(|(_, token)| token)
+            .filter(|token| token.user_id == user_id)
+            .ok_or_else(|| DbError::NotFound(format!("API token {}", token_id)))?;
+
+        token.revoked_at.get_or_insert_with(Utc::now);
+        Ok(token.clone())
+    }
+
+    async fn touch(&self, token_id: &str) -> Result<(), DbError> {
+        if let Some((_, token)) = self.tokens.write().unwrap().get_mut(token_id) {
+            token.last_used_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError> {
+        let mut tokens = self.tokens.write().unwrap();
+        let before = tokens.len();
+        tokens.retain(|_, (_, token)| token.user_id != user_id);
+        Ok((before - tokens.len()) as u64)
+    }
+}
+
+/// Identity storage in a process-local list, for tests
+///
+/// Enforces the unique (provider, subject) rule of the ArcadeDB index.
+#[derive(Default)]
+pub struct InMemoryAuthIdentityRepository {
+    identities: RwLock<Vec<AuthIdentity>>,
+}
+
+impl InMemoryAuthIdentityRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AuthIdentityRepository for InMemoryAuthIdentityRepository {
+    async fn find(&self, provider: &str, subject: &str) -> Result<Option<AuthIdentity>, DbError> {
+        Ok(self
+            .identities
+            .read()
+            .unwrap()
+            .iter()
+            .find(|identity| identity.provider == provider && identity.subject == subject)
+            .cloned())
+    }
+
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<AuthIdentity>, DbError> {
+        Ok(self
+            .identities
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|identity| identity.user_id == user_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, identity: NewAuthIdentity) -> Result<AuthIdentity, DbError> {
+        let mut identities = self.identities.write().unwrap();
+
+        if identities
+            .iter()
+            .any(|existing| existing.provider == identity.provider && existing.subject == identity.subject)
+        {
+            return Err(DbError::Conflict(format!(
+                "duplicate key in index 'AuthIdentity_provider_subject_idx': {}, {}",
+                identity.provider, identity.subject
+            )));
+        }
+
+        let created = AuthIdentity {
+            provider: identity.provider,
+            subject: identity.subject,
+            user_id: identity.user_id,
+            linked_at: Some(Utc::now()),
+        };
+
+        identities.push(created.clone());
+        Ok(created)
+    }
+
+    async fn delete(&self, user_id: &str, provider: &str, subject: &str) -> Result<(), DbError> {
+        let mut identities = self.identities.write().unwrap();
+        let before = identities.len();
+        identities.retain(|identity| {
+            !(identity.user_id == user_id && identity.provider == provider && identity.subject == subject)
+        });
+
+        if identities.len() == before {
+            return Err(DbError::NotFound(format!("{} identity {}", provider, subject)));
+        }
+
+        Ok(())
+    }
+
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError> {
+        let mut identities = self.identities.write().unwrap();
+        let before = identities.len();
+        identities.retain(|identity| identity.user_id != user_id);
+        Ok((before - identities.len()) as u64)
+    }
+}
+
+/// Erasure records in a process-local list, for tests
+#[derive(Default)]
+pub struct InMemoryErasureRepository {
+    records: RwLock<Vec<ErasureRecord>>,
+}
+
+impl InMemoryErasureRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ErasureRepository for InMemoryErasureRepository {
+    async fn create(&self, record: NewErasureRecord) -> Result<ErasureRecord, DbError> {
+        let mut records = self.records.write().unwrap();
+
+        if records.iter().any(|existing| existing.user_id == record.user_id) {
+            return Err(DbError::Conflict(format!(
+                "duplicate key in index 'ErasureRecord_userId_idx': {}",
+                record.user_id
+            )));
+        }
+
+        let created = ErasureRecord {
+            user_id: record.user_id,
+            subject_hashes: record.subject_hashes,
+            requested_by: record.requested_by,
+            requested_at: record.requested_at,
+            erased_at: Some(Utc::now()),
+        };
+
+        records.push(created.clone());
+        Ok(created)
+    }
+
+    async fn is_erased(&self, subject_hash: &str) -> Result<bool, DbError> {
+        Ok(self
+            .records
+            .read()
+            .unwrap()
+            .iter()
+            .any(|record| record.subject_hashes.iter().any(|hash| hash == subject_hash)))
+    }
+}
+
+/// Export jobs in a process-local map, for tests
+#[derive(Default)]
+pub struct InMemoryExportRepository {
+    /// Jobs with their archives, keyed by exportId
+    jobs: RwLock<HashMap<String, (ExportJob, Option<String>)>>,
+}
+
+impl InMemoryExportRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&self, export_id: &str, apply: impl FnOnce(&mut ExportJob, &mut Option<String>)) {
+        if let Some((job, archive)) = self.jobs.write().unwrap().get_mut(export_id) {
+            apply(job, archive);
+        }
+    }
+}
+
+#[async_trait]
+impl ExportRepository for InMemoryExportRepository {
+    async fn create(&self, export_id: &str, user_id: &str) -> Result<ExportJob, DbError> {
+        let mut jobs = self.jobs.write().unwrap();
+
+        if jobs.contains_key(export_id) {
+            return Err(DbError::Conflict(format!(
+                "duplicate key in index 'ExportJob_exportId_idx': {}",
+                export_id
+            )));
+        }
+
+        let created = ExportJob {
+            export_id: export_id.to_string(),
+            user_id: user_id.to_string(),
+            status: ExportStatus::Pending,
+            requested_at: Some(Utc::now()),
+            completed_at: None,
+            expires_at: None,
+            error: None,
+        };
+
+        jobs.insert(export_id.to_string(), (created.clone(), None));
+        Ok(created)
+    }
+
+    async fn find(&self, user_id: &str, export_id: &str) -> Result<Option<ExportJob>, DbError> {
+        Ok(self
+            .jobs
+            .read()
+            .unwrap()
+            .get(export_id)
+            .map(|(job, _)| job)
+            .filter(|job| job.user_id == user_id)
+            .cloned())
+    }
+
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<ExportJob>, DbError> {
+        let mut jobs: Vec<ExportJob> = self
+            .jobs
+            .read()
+            .unwrap()
+            .values()
+            .map(|(job, _)| job)
+            .filter(|job| job.user_id == user_id)
+            .cloned()
+            .collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.requested_at));
+        Ok(jobs)
+    }
+
+    async fn mark_running(&self, export_id: &str) -> Result<(), DbError> {
+        self.update(export_id, |job, _| job.status = ExportStatus::Running);
+        Ok(())
+    }
+
+    async fn complete(&self, export_id: &str, archive: String, expires_at: DateTime<Utc>) -> Result<(), DbError> {
+        self.update(export_id, |job, stored| {
+            job.status = ExportStatus::Ready;
+            job.completed_at = Some(Utc::now());
+            job.expires_at = Some(expires_at);
+            *stored = Some(archive);
+        });
+        Ok(())
+    }
+
+    async fn fail(&self, export_id: &str, error: &str) -> Result<(), DbError> {
+        self.update(export_id, |job, _| {
+            job.status = ExportStatus::Failed;
+            job.completed_at = Some(Utc::now());
+            job.error = Some(error.to_string());
+        });
+        Ok(())
+    }
+
+    async fn archive(&self, export_id: &str) -> Result<Option<String>, DbError> {
+        Ok(self
+            .jobs
+            .read()
+            .unwrap()
+            .get(export_id)
+            .filter(|(job, _)| job.status == ExportStatus::Ready)
+            .and_then(|(_, archive)| archive.clone()))
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64, DbError> {
+        let mut jobs = self.jobs.write().unwrap();
+        let before = jobs.len();
+        jobs.retain(|_, (job, _)| job.expires_at.is_none_or(|at| at > now));
+        Ok((before - jobs.len()) as u64)
+    }
+
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError> {
+        let mut jobs = self.jobs.write().unwrap();
+        let before = jobs.len();
+        jobs.retain(|_, (job, _)| job.user_id != user_id);
+        Ok((before - jobs.len()) as u64)
+    }
+}
+
+/// Released handles in a process-local list, for tests
+#[derive(Default)]
+pub struct InMemoryHandleHistoryRepository {
+    entries: RwLock<Vec<HandleHistory>>,
+}
+
+impl InMemoryHandleHistoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl HandleHistoryRepository for InMemoryHandleHistoryRepository {
+    async fn record(&self, entry: NewHandleHistory) -> Result<HandleHistory, DbError> {
+        let created = HandleHistory {
+            handle: entry.handle,
+            user_id: entry.user_id,
+            released_at: Some(Utc::now()),
+            expires_at: Some(entry.expires_at),
+        };
+
+        self.entries.write().unwrap().push(created.clone());
+        Ok(created)
+    }
+
+    async fn find_active(&self, handle: &str, now: DateTime<Utc>) -> Result<Option<HandleHistory>, DbError> {
+        let handle = handle.to_lowercase();
+        Ok(self
+            .entries
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.handle.to_lowercase() == handle && entry.expires_at.is_some_and(|at| at > now))
+            .max_by_key(|entry| entry.released_at)
+            .cloned())
+    }
+
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<HandleHistory>, DbError> {
+        let mut entries: Vec<HandleHistory> = self
+            .entries
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.user_id == user_id)
+            .cloned()
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.released_at));
+        Ok(entries)
+    }
+
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64, DbError> {
+        let mut entries = self.entries.write().unwrap();
+        let before = entries.len();
+        entries.retain(|entry| entry.user_id != user_id);
+        Ok((before - entries.len()) as u64)
+    }
+}
@@ -0,0 +1,68 @@
+// Repository module
+// Persistence behind traits so handlers don't depend on a specific store
+
+use std::sync::Arc;
+
+use crate::db::connection::DatabaseConnection;
+
+pub mod api_token;
+pub mod arcade;
+pub mod erasure;
+pub mod export;
+pub mod handle;
+pub mod identity;
+#[cfg(feature = "test-support")]
+pub mod memory;
+pub mod profile;
+
+pub use api_token::{ApiToken, ApiTokenRepository, NewApiToken};
+pub use arcade::{
+    ArcadeApiTokenRepository, ArcadeAuthIdentityRepository, ArcadeErasureRepository, ArcadeExportRepository,
+    ArcadeHandleHistoryRepository, ArcadeProfileRepository,
+};
+pub use erasure::{subject_hash, ErasureRecord, ErasureRepository, NewErasureRecord};
+pub use export::{ExportJob, ExportRepository, ExportStatus};
+pub use handle::{HandleHistory, HandleHistoryRepository, NewHandleHistory};
+pub use identity::{
+    find_by_identity, find_or_create_by_identity, unlink_identity, AuthIdentity, AuthIdentityRepository,
+    NewAuthIdentity, UnlinkError,
+};
+pub use profile::{NewProfile, Profile, ProfileRepository, ProfileUpdate};
+
+/// The repositories held by `AppState`
+#[derive(Clone)]
+pub struct Repositories {
+    pub profiles: Arc<dyn ProfileRepository>,
+    pub identities: Arc<dyn AuthIdentityRepository>,
+    pub api_tokens: Arc<dyn ApiTokenRepository>,
+    pub erasures: Arc<dyn ErasureRepository>,
+    pub exports: Arc<dyn ExportRepository>,
+    pub handle_history: Arc<dyn HandleHistoryRepository>,
+}
+
+impl Repositories {
+    /// ArcadeDB repositories sharing one connection
+    pub fn arcade(db: &DatabaseConnection) -> Self {
+        Self {
+            profiles: Arc::new(ArcadeProfileRepository::new(db.clone())),
+            identities: Arc::new(ArcadeAuthIdentityRepository::new(db.clone())),
+            api_tokens: Arc::new(ArcadeApiTokenRepository::new(db.clone())),
+            erasures: Arc::new(ArcadeErasureRepository::new(db.clone())),
+            exports: Arc::new(ArcadeExportRepository::new(db.clone())),
+            handle_history: Arc::new(ArcadeHandleHistoryRepository::new(db.clone())),
+        }
+    }
+
+    /// Process-local repositories, for tests
+    #[cfg(feature = "test-support")]
+    pub fn in_memory() -> Self {
+        Self {
+            profiles: Arc::new(memory::InMemoryProfileRepository::new()),
+            identities: Arc::new(memory::InMemoryAuthIdentityRepository::new()),
+            api_tokens: Arc::new(memory::InMemoryApiTokenRepository::new()),
+            erasures: Arc::new(memory::InMemoryErasureRepository::new()),
+            exports: Arc::new(memory::InMemoryExportRepository::new()),
+            handle_history: Arc::new(memory::InMemoryHandleHistoryRepository::new()),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::auth::roles::{self, Role};
+use crate::db::error::DbError;
+use crate::patch::Patch;
+use crate::db::timestamp;
+
+/// A stored Profile record
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub user_id: String,
+    #[serde(default)]
+    pub clerk_id: Option<String>,
+    /// URL-safe name for mentions and profile URLs, as the user typed it
+    #[serde(default)]
+    pub handle: Option<String>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub handle_changed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub about_me: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "roles::deserialize_roles")]
+    pub roles: Vec<Role>,
+    /// Set once the profile has been tombstoned
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub deletion_requested_at: Option<DateTime<Utc>>,
+    /// When the account will be erased unless the deletion is cancelled
+    #[serde(default, deserialize_with = "timestamp::deserialize_option")]
+    pub deletion_scheduled_for: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub deletion_requested_by: Option<String>,
+    /// Incremented by every write, for optimistic concurrency
+    #[serde(default)]
+    pub version: u64,
+}
+
+impl Profile {
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+}
+
+/// Fields required to create a profile
+#[derive(Debug, Clone)]
+pub struct NewProfile {
+    pub user_id: String,
+    pub clerk_id: Option<String>,
+    pub display_name: Option<String>,
+}
+
+/// Fields to change on an existing profile; [`Patch::Absent`] leaves a field
+/// unchanged and [`Patch::Null`] clears it
+#[derive(Debug, Clone, Default)]
+pub struct ProfileUpdate {
+    pub display_name: Patch<String>,
+    pub about_me: Patch<String>,
+    pub avatar_url: Patch<String>,
+}
+
+impl ProfileUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.display_name.is_absent() && self.about_me.is_absent() && self.avatar_url.is_absent()
+    }
+}
+
+/// Storage for Profile records
+///
+/// Implementations report a duplicate userId, clerkId or handle as
+/// [`DbError::Conflict`] and a missing profile on update/delete as
+/// [`DbError::NotFound`]. Every write increments the profile's `version`.
+#[async_trait]
+pub trait ProfileRepository: Send + Sync {
+    async fn find_by_user_id(&self, user_id: &str) -> Result<Option<Profile>, DbError>;
+
+    async fn find_by_clerk_id(&self, clerk_id: &str) -> Result<Option<Profile>, DbError>;
+
+    /// The profile with this handle, ignoring case
+    async fn find_by_handle(&self, handle: &str) -> Result<Option<Profile>, DbError>;
+
+    async fn create(&self, profile: NewProfile) -> Result<Profile, DbError>;
+
+    /// Apply `update` and return the profile as stored afterwards
+    ///
+    /// With `expected_version`, the update only applies while the profile is
+    /// still at that version; otherwise it is a [`DbError::Conflict`].
+    async fn update(
+        &self,
+        user_id: &str,
+        update: ProfileUpdate,
+        expected_version: Option<u64>,
+    ) -> Result<Profile, DbError>;
+
+    async fn delete(&self, user_id: &str) -> Result<(), DbError>;
+
+    /// Mark the profile deleted and clear its personal fields, keeping the
+    /// row so userId references stay valid
+    async fn tombstone(&self, user_id: &str) -> Result<Profile, DbError>;
+
+    /// Set the profile's handle; a handle another profile holds, in any
+    /// case, is a [`DbError::Conflict`]
+    async fn set_handle(&self, user_id: &str, handle: &str) -> Result<Profile, DbError>;
+
+    /// Replace the profile's roles
+    async fn set_roles(&self, user_id: &str, roles: &[Role]) -> Result<Profile, DbError>;
+
+    /// Schedule the account's erasure at `scheduled_for`, replacing any
+    /// earlier schedule
+    async fn schedule_deletion(
+        &self,
+        user_id: &str,
+        requested_by: &str,
+        scheduled_for: DateTime<Utc>,
+    ) -> Result<Profile, DbError>;
+
+    /// Clear a scheduled deletion
+    async fn cancel_deletion(&self, user_id: &str) -> Result<Profile, DbError>;
+
+    /// Profiles whose scheduled deletion is at or before `now`
+    async fn due_for_deletion(&self, now: DateTime<Utc>) -> Result<Vec<Profile>, DbError>;
+}
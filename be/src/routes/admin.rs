@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::AppState;
+use crate::auth::{Admin, RequireRole, Role};
+use crate::erasure::Requester;
+use crate::routes::deletion::{self, DeletionResponse};
+
+#[derive(Deserialize)]
+pub struct SetRolesRequest {
+    pub roles: Vec<Role>,
+}
+
+#[derive(Serialize)]
+pub struct RolesResponse {
+    pub user_id: String,
+    pub roles: Vec<Role>,
+}
+
+/// PUT /api/v1/admin/profiles/:user_id/roles - Replace a user's roles (admin only)
+pub async fn set_profile_roles(
+    State(app_state): State<AppState>,
+    RequireRole(admin, ..): RequireRole<Admin>,
+    Path(user_id): Path<String>,
+    Json(payload): Json<SetRolesRequest>,
+) -> Result<Json<RolesResponse>, StatusCode> {
+    let mut roles = payload.roles;
+    roles.sort();
+    roles.dedup();
+
+    let profile = app_state
+        .profiles
+        .set_roles(&user_id, &roles)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set roles for {}: {}", user_id, e);
+            e.status_code()
+        })?;
+
+    tracing::info!(
+        "Admin {} set roles of {} to {:?}",
+        admin.user_id,
+        profile.user_id,
+        profile.roles
+    );
+
+    Ok(Json(RolesResponse {
+        user_id: profile.user_id,
+        roles: profile.roles,
+    }))
+}
+
+/// DELETE /api/v1/admin/profiles/:user_id - Delete a user's account after the
+/// grace period (admin only)
+pub async fn delete_profile(
+    State(app_state): State<AppState>,
+    RequireRole(admin, ..): RequireRole<Admin>,
+    Path(user_id): Path<String>,
+) -> Result<(StatusCode, Json<DeletionResponse>), StatusCode> {
+    deletion::schedule(&app_state, &user_id, Requester::Admin(admin.user_id)).await
+}
+
+/// DELETE /api/v1/admin/profiles/:user_id/deletion - Cancel a user's
+/// scheduled deletion (admin only)
+pub async fn cancel_profile_deletion(
+    State(app_state): State<AppState>,
+    RequireRole(admin, ..): RequireRole<Admin>,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let status = deletion::cancel(&app_state, &user_id).await?;
+    tracing::info!("Admin {} cancelled deletion of {}", admin.user_id, user_id);
+    Ok(status)
+}
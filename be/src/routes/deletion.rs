@@ -0,0 +1,115 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::app_state::AppState;
+use crate::auth::{ReadProfile, RequireScope, RequireSession};
+use crate::erasure::{self, Requester};
+use crate::repository::Profile;
+
+#[derive(Serialize)]
+pub struct DeletionResponse {
+    pub user_id: String,
+    pub requested_at: Option<DateTime<Utc>>,
+    /// When the account will be erased unless cancelled
+    pub scheduled_for: DateTime<Utc>,
+    pub requested_by: Option<String>,
+}
+
+impl DeletionResponse {
+    /// `None` unless a deletion is scheduled
+    fn from_profile(profile: Profile) -> Option<Self> {
+        Some(Self {
+            scheduled_for: profile.deletion_scheduled_for?,
+            user_id: profile.user_id,
+            requested_at: profile.deletion_requested_at,
+            requested_by: profile.deletion_requested_by,
+        })
+    }
+}
+
+/// Schedule the erasure of `user_id`'s account after the grace period
+pub(crate) async fn schedule(
+    app_state: &AppState,
+    user_id: &str,
+    requester: Requester,
+) -> Result<(StatusCode, Json<DeletionResponse>), StatusCode> {
+    let profile = app_state
+        .profiles
+        .find_by_user_id(user_id)
+        .await
+        .map_err(|e| e.status_code())?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let grace = app_state.config.accounts.deletion_grace;
+    let profile = erasure::request_deletion(app_state.profiles.as_ref(), profile, &requester, grace)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to schedule deletion of {}: {}", user_id, e);
+            e.status_code()
+        })?;
+
+    let response = DeletionResponse::from_profile(profile).ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok((StatusCode::ACCEPTED, Json(response)))
+}
+
+/// Cancel the scheduled deletion of `user_id`'s account
+pub(crate) async fn cancel(app_state: &AppState, user_id: &str) -> Result<StatusCode, StatusCode> {
+    let profile = app_state
+        .profiles
+        .find_by_user_id(user_id)
+        .await
+        .map_err(|e| e.status_code())?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if profile.deletion_scheduled_for.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    // Deleted in Clerk; there is no account left to keep
+    if profile.is_deleted() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    app_state.profiles.cancel_deletion(user_id).await.map_err(|e| {
+        tracing::error!("Failed to cancel deletion of {}: {}", user_id, e);
+        e.status_code()
+    })?;
+
+    tracing::info!("Deletion of userId={} cancelled", user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /api/v1/profiles/me - Delete the current user's account after a
+/// grace period
+pub async fn delete_current_profile(
+    State(app_state): State<AppState>,
+    RequireSession(user): RequireSession,
+) -> Result<(StatusCode, Json<DeletionResponse>), StatusCode> {
+    schedule(&app_state, &user.user_id, Requester::User).await
+}
+
+/// GET /api/v1/profiles/me/deletion - The current user's pending deletion
+pub async fn get_current_deletion(
+    State(app_state): State<AppState>,
+    RequireScope(user, ..): RequireScope<ReadProfile>,
+) -> Result<Json<DeletionResponse>, StatusCode> {
+    let profile = app_state
+        .profiles
+        .find_by_user_id(&user.user_id)
+        .await
+        .map_err(|e| e.status_code())?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    DeletionResponse::from_profile(profile)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// DELETE /api/v1/profiles/me/deletion - Cancel the current user's deletion
+pub async fn cancel_current_deletion(
+    State(app_state): State<AppState>,
+    RequireSession(user): RequireSession,
+) -> Result<StatusCode, StatusCode> {
+    cancel(&app_state, &user.user_id).await
+}
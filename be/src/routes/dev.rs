@@ -0,0 +1,54 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::app_state::AppState;
+use crate::auth::dev::DevAuth;
+
+/// Longest lifetime a dev token may be minted with
+const MAX_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Deserialize)]
+pub struct MintTokenRequest {
+    pub subject: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    /// Defaults to one hour
+    pub ttl_seconds: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct MintTokenResponse {
+    pub token: String,
+    /// Unix timestamp
+    pub expires_at: u64,
+}
+
+/// POST /api/v1/dev/tokens - Mint a self-signed token (dev auth only)
+pub async fn mint_dev_token(
+    State(app_state): State<AppState>,
+    Json(payload): Json<MintTokenRequest>,
+) -> Result<Json<MintTokenResponse>, StatusCode> {
+    // Only routed when dev auth is configured, so the secret is present
+    let secret = app_state
+        .config
+        .auth
+        .dev_secret
+        .as_ref()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if payload.subject.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let ttl = Duration::from_secs(payload.ttl_seconds.unwrap_or(60 * 60).min(MAX_TTL_SECONDS));
+
+    let (token, expires_at) = DevAuth::new(secret.expose())
+        .mint(&payload.subject, payload.email, payload.name, ttl)
+        .map_err(|e| {
+            tracing::error!("Failed to mint dev token: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(MintTokenResponse { token, expires_at }))
+}
@@ -0,0 +1,158 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::app_state::AppState;
+use crate::auth::{ReadProfile, RequireScope, RequireSession};
+use crate::export;
+use crate::repository::{ExportJob, ExportStatus};
+
+#[derive(Serialize)]
+pub struct ExportResponse {
+    pub export_id: String,
+    pub status: ExportStatus,
+    pub requested_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    /// Set once the archive is ready
+    pub download_url: Option<String>,
+}
+
+impl From<ExportJob> for ExportResponse {
+    fn from(job: ExportJob) -> Self {
+        let download_url = (job.status == ExportStatus::Ready)
+            .then(|| format!("/api/v1/profiles/me/exports/{}/download", job.export_id));
+        Self {
+            export_id: job.export_id,
+            status: job.status,
+            requested_at: job.requested_at,
+            completed_at: job.completed_at,
+            expires_at: job.expires_at,
+            error: job.error,
+            download_url,
+        }
+    }
+}
+
+/// POST /api/v1/profiles/me/exports - Start exporting the current user's data
+///
+/// While an export is still being built, it is returned instead of starting
+/// another one.
+pub async fn start_export(
+    State(app_state): State<AppState>,
+    RequireSession(user): RequireSession,
+) -> Result<(StatusCode, Json<ExportResponse>), StatusCode> {
+    let jobs = app_state
+        .exports
+        .list_for_user(&user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list exports: {}", e);
+            e.status_code()
+        })?;
+
+    let stale_before = Utc::now() - chrono::Duration::seconds(export::STALE_AFTER.as_secs() as i64);
+    if let Some(job) = jobs
+        .into_iter()
+        .find(|job| job.status.is_in_progress() && job.requested_at.is_some_and(|at| at > stale_before))
+    {
+        return Ok((StatusCode::ACCEPTED, Json(job.into())));
+    }
+
+    let job = app_state
+        .exports
+        .create(&uuid::Uuid::new_v4().to_string(), &user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create export: {}", e);
+            e.status_code()
+        })?;
+
+    tracing::info!("User {} started export {}", user.user_id, job.export_id);
+    export::spawn_export(app_state.clone(), job.export_id.clone(), user.user_id);
+
+    Ok((StatusCode::ACCEPTED, Json(job.into())))
+}
+
+/// GET /api/v1/profiles/me/exports - List the current user's exports
+pub async fn list_exports(
+    State(app_state): State<AppState>,
+    RequireScope(user, ..): RequireScope<ReadProfile>,
+) -> Result<Json<Vec<ExportResponse>>, StatusCode> {
+    let jobs = app_state
+        .exports
+        .list_for_user(&user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list exports: {}", e);
+            e.status_code()
+        })?;
+
+    Ok(Json(jobs.into_iter().map(ExportResponse::from).collect()))
+}
+
+/// GET /api/v1/profiles/me/exports/:export_id - Poll one of the current
+/// user's exports
+pub async fn get_export(
+    State(app_state): State<AppState>,
+    RequireScope(user, ..): RequireScope<ReadProfile>,
+    Path(export_id): Path<String>,
+) -> Result<Json<ExportResponse>, StatusCode> {
+    let job = app_state
+        .exports
+        .find(&user.user_id, &export_id)
+        .await
+        .map_err(|e| e.status_code())?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(job.into()))
+}
+
+/// GET /api/v1/profiles/me/exports/:export_id/download - Download a ready
+/// export archive
+///
+/// 409 while the export is unfinished or failed, 410 once it has expired.
+pub async fn download_export(
+    State(app_state): State<AppState>,
+    RequireScope(user, ..): RequireScope<ReadProfile>,
+    Path(export_id): Path<String>,
+) -> Result<Response, StatusCode> {
+    let job = app_state
+        .exports
+        .find(&user.user_id, &export_id)
+        .await
+        .map_err(|e| e.status_code())?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if job.status != ExportStatus::Ready {
+        return Err(StatusCode::CONFLICT);
+    }
+    // The cleanup job runs hourly; don't serve archives it hasn't reached yet
+    if job.expires_at.is_some_and(|at| at <= Utc::now()) {
+        return Err(StatusCode::GONE);
+    }
+
+    let archive = app_state
+        .exports
+        .archive(&export_id)
+        .await
+        .map_err(|e| e.status_code())?
+        .ok_or(StatusCode::GONE)?;
+
+    let filename = format!("cynnycty-export-{}.json", export_id);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        archive,
+    )
+        .into_response())
+}
@@ -0,0 +1,106 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::AppState;
+use crate::auth::middleware::OptionalAuthExtension;
+use crate::auth::{RequireScope, WriteProfile};
+use crate::handle::{self, ChangeError, Unavailable};
+use crate::routes::profiles::{load_profile, ProfileResponse};
+use crate::validation::ValidationErrors;
+
+#[derive(Serialize)]
+pub struct AvailabilityResponse {
+    pub handle: String,
+    pub available: bool,
+    /// Why it isn't available: a validation code such as `reserved`, or
+    /// `taken`
+    pub reason: Option<&'static str>,
+}
+
+/// GET /api/v1/handles/:handle/availability - Whether a handle can be taken
+///
+/// Signed in, the viewer's own current and recently released handles count
+/// as available.
+pub async fn check_handle_availability(
+    State(app_state): State<AppState>,
+    Extension(OptionalAuthExtension(viewer)): Extension<OptionalAuthExtension>,
+    Path(handle): Path<String>,
+) -> Result<Json<AvailabilityResponse>, StatusCode> {
+    let viewer_id = viewer.as_ref().map(|user| user.user_id.as_str());
+    let result = handle::availability(&app_state, &handle, viewer_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check handle availability: {}", e);
+            e.status_code()
+        })?;
+
+    Ok(Json(AvailabilityResponse {
+        handle,
+        available: result.is_ok(),
+        reason: result.err().map(|reason| reason.code()),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct ChangeHandleRequest {
+    pub handle: String,
+}
+
+/// PUT /api/v1/profiles/me/handle - Change the current user's handle
+///
+/// 422 with a `handle` field error for a handle that breaks the rules, 409 if it is taken, 429 during
+/// the cooldown after the previous change.
+pub async fn change_current_handle(
+    State(app_state): State<AppState>,
+    RequireScope(user, ..): RequireScope<WriteProfile>,
+    Json(payload): Json<ChangeHandleRequest>,
+) -> Result<ProfileResponse, Response> {
+    let profile = load_profile(&app_state, &user.user_id)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let profile = handle::change_handle(&app_state, profile, payload.handle.trim())
+        .await
+        .map_err(change_error)?;
+
+    Ok(ProfileResponse::new(profile, user))
+}
+
+fn change_error(e: ChangeError) -> Response {
+    match e {
+        ChangeError::Unavailable(Unavailable::Invalid(e)) => {
+            ValidationErrors::field("handle", e.code(), e.to_string()).into_response()
+        }
+        ChangeError::Unavailable(Unavailable::Taken) => {
+            let body = serde_json::json!({
+                "error": "handle_taken",
+                "message": "This handle is taken",
+            });
+            (StatusCode::CONFLICT, Json(body)).into_response()
+        }
+        ChangeError::Cooldown(next_change_at) => {
+            let body = serde_json::json!({
+                "error": "handle_change_cooldown",
+                "message": "The handle was changed too recently",
+                "next_change_at": next_change_at,
+            });
+            let retry_after = (next_change_at - Utc::now()).num_seconds().max(1);
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                Json(body),
+            )
+                .into_response()
+        }
+        ChangeError::Db(e) => {
+            tracing::error!("Failed to change handle: {}", e);
+            e.status_code().into_response()
+        }
+    }
+}
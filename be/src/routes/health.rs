@@ -1,15 +1,32 @@
-use axum::Json;
-use serde::{Deserialize, Serialize};
+use axum::{extract::State, Json};
+use serde::Serialize;
 
-#[derive(Serialize, Deserialize)]
+use crate::app_state::AppState;
+use crate::auth::jwks::JwksMetrics;
+use crate::dependencies::DependenciesReport;
+
+#[derive(Serialize)]
 pub struct HealthResponse {
     pub status: String,
     pub message: String,
+    pub dependencies: DependenciesReport,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_keys: Option<JwksMetrics>,
 }
 
-pub async fn health_check() -> Json<HealthResponse> {
+/// Liveness check; always 200, with `status: "degraded"` while a dependency is down
+pub async fn health_check(State(app_state): State<AppState>) -> Json<HealthResponse> {
+    let dependencies = &app_state.dependencies;
+    let (status, message) = if dependencies.all_up() {
+        ("ok", "Cynnycty backend is running")
+    } else {
+        ("degraded", "Cynnycty backend is running with unavailable dependencies")
+    };
+
     Json(HealthResponse {
-        status: "ok".to_string(),
-        message: "Cynnycty backend is running".to_string(),
+        status: status.to_string(),
+        message: message.to_string(),
+        dependencies: dependencies.report(),
+        auth_keys: app_state.auth.key_metrics(),
     })
 }
@@ -0,0 +1,143 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::AppState;
+use crate::auth::RequireSession;
+use crate::db::error::DbError;
+use crate::repository::identity;
+use crate::repository::{AuthIdentity, NewAuthIdentity, UnlinkError};
+
+#[derive(Serialize)]
+pub struct IdentityResponse {
+    pub provider: String,
+    pub subject: String,
+    pub linked_at: Option<DateTime<Utc>>,
+}
+
+impl From<AuthIdentity> for IdentityResponse {
+    fn from(identity: AuthIdentity) -> Self {
+        Self {
+            provider: identity.provider,
+            subject: identity.subject,
+            linked_at: identity.linked_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LinkIdentityRequest {
+    /// A session token for the identity to link, proving the caller owns it
+    pub token: String,
+}
+
+fn conflict(message: &str) -> Response {
+    let body = serde_json::json!({
+        "error": "conflict",
+        "message": message,
+    });
+    (StatusCode::CONFLICT, Json(body)).into_response()
+}
+
+/// GET /api/v1/identities - List the identities linked to the current user
+pub async fn list_identities(
+    State(app_state): State<AppState>,
+    RequireSession(user): RequireSession,
+) -> Result<Json<Vec<IdentityResponse>>, StatusCode> {
+    let identities = app_state
+        .identities
+        .list_for_user(&user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list identities: {}", e);
+            e.status_code()
+        })?;
+
+    Ok(Json(identities.into_iter().map(IdentityResponse::from).collect()))
+}
+
+/// POST /api/v1/identities - Link another identity to the current user
+///
+/// The identity is taken from a verified session token, so only someone
+/// signed in as both can link them. An identity that already has its own
+/// profile cannot be linked.
+///
+/// The token is verified by `app_state.auth`, the one configured provider,
+/// so this links another account of the same provider only; tokens from any
+/// other provider are rejected with 401.
+pub async fn link_identity(
+    State(app_state): State<AppState>,
+    RequireSession(user): RequireSession,
+    Json(payload): Json<LinkIdentityRequest>,
+) -> Result<(StatusCode, Json<IdentityResponse>), Response> {
+    let identity = app_state.auth.verify(&payload.token).await.map_err(|e| {
+        tracing::warn!("Identity link token rejected ({}): {}", e.code(), e);
+        e.into_response()
+    })?;
+
+    let existing = app_state
+        .identities
+        .find(&identity.provider, &identity.subject)
+        .await
+        .map_err(|e| e.status_code().into_response())?;
+
+    match existing {
+        Some(linked) if linked.user_id == user.user_id => return Ok((StatusCode::OK, Json(linked.into()))),
+        Some(_) => return Err(conflict("This identity is linked to another account")),
+        None => {}
+    }
+
+    let linked = app_state
+        .identities
+        .create(NewAuthIdentity {
+            provider: identity.provider,
+            subject: identity.subject,
+            user_id: user.user_id.clone(),
+        })
+        .await
+        .map_err(|e| match e {
+            DbError::Conflict(_) => conflict("This identity is linked to another account"),
+            e => {
+                tracing::error!("Failed to link identity: {}", e);
+                e.status_code().into_response()
+            }
+        })?;
+
+    tracing::info!("Linked {} identity to userId={}", linked.provider, user.user_id);
+
+    Ok((StatusCode::CREATED, Json(linked.into())))
+}
+
+/// DELETE /api/v1/identities/:provider/:subject - Unlink an identity
+///
+/// The last identity, and the one the current session signed in with,
+/// cannot be unlinked.
+pub async fn unlink_identity(
+    State(app_state): State<AppState>,
+    RequireSession(user): RequireSession,
+    Path((provider, subject)): Path<(String, String)>,
+) -> Result<StatusCode, Response> {
+    if provider == user.provider && subject == user.subject {
+        return Err(conflict("Cannot unlink the identity you are signed in with"));
+    }
+
+    identity::unlink_identity(app_state.identities.as_ref(), &user.user_id, &provider, &subject)
+        .await
+        .map_err(|e| match e {
+            UnlinkError::NotFound => StatusCode::NOT_FOUND.into_response(),
+            UnlinkError::LastIdentity => conflict("An account must keep at least one identity"),
+            UnlinkError::Db(e) => {
+                tracing::error!("Failed to unlink identity: {}", e);
+                e.status_code().into_response()
+            }
+        })?;
+
+    tracing::info!("Unlinked {} identity from userId={}", provider, user.user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
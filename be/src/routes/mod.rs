@@ -1,6 +1,129 @@
 // Routes module
 // This will contain all API route handlers
 
+pub mod admin;
+pub mod handles;
 pub mod health;
+pub mod identities;
 pub mod database;
+pub mod deletion;
+pub mod dev;
+pub mod export;
 pub mod profiles;
+pub mod session;
+pub mod tokens;
+pub mod webhooks;
+
+use axum::{
+    middleware,
+    routing::{delete, get, patch, post, put},
+    Router,
+};
+use tower_http::cors::{Any, CorsLayer};
+
+use crate::app_state::AppState;
+use crate::auth::{auth_middleware, optional_auth_middleware};
+use crate::config::AuthProviderKind;
+use crate::dependencies::{require_database, require_dependencies};
+use admin::{cancel_profile_deletion, delete_profile, set_profile_roles};
+use database::database_health_check;
+use deletion::{cancel_current_deletion, delete_current_profile, get_current_deletion};
+use dev::mint_dev_token;
+use export::{download_export, get_export, list_exports, start_export};
+use handles::{change_current_handle, check_handle_availability};
+use health::health_check;
+use identities::{link_identity, list_identities, unlink_identity};
+use profiles::{
+    get_current_profile, get_profile, get_profile_by_handle, patch_current_profile, update_current_profile,
+};
+use session::get_session;
+use tokens::{create_token, list_tokens, revoke_token};
+use webhooks::clerk_webhook;
+
+/// Build the full application router
+pub fn router(app_state: AppState) -> Router {
+    // Public routes (no auth required)
+    let mut public_routes = Router::new()
+        .route("/health", get(health_check))
+        .route("/api/v1/health", get(health_check))
+        .route("/api/v1/db/health", get(database_health_check));
+
+    // Clerk webhooks verify their own Svix signature
+    if app_state.config.clerk.webhook_secret.is_some() {
+        public_routes = public_routes.route("/api/v1/webhooks/clerk", post(clerk_webhook));
+    }
+
+    // Token minting exists only when dev auth is explicitly configured
+    if app_state.config.auth.provider == AuthProviderKind::Dev {
+        public_routes = public_routes.route("/api/v1/dev/tokens", post(mint_dev_token));
+    }
+
+    // Public data (no auth; responses are the same for everyone, so CDNs
+    // may cache them). Only the database has to be up: these keep working
+    // while the auth provider's keys are unreachable.
+    let public_data_routes = Router::new()
+        .route("/api/v1/profiles/:user_id", get(get_profile))
+        .route("/api/v1/profiles/by-handle/:handle", get(get_profile_by_handle))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.dependencies.clone(),
+            require_database,
+        ));
+
+    // Viewer-aware routes (auth optional; an invalid token is still 401)
+    let viewer_routes = Router::new()
+        .route("/api/v1/session", get(get_session))
+        .route("/api/v1/handles/:handle/availability", get(check_handle_availability))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            optional_auth_middleware,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.dependencies.clone(),
+            require_dependencies,
+        ));
+
+    // Protected routes (auth required)
+    let protected_routes = Router::new()
+        .route("/api/v1/profiles/me", get(get_current_profile))
+        .route("/api/v1/profiles/me", put(update_current_profile))
+        .route("/api/v1/profiles/me", patch(patch_current_profile))
+        .route("/api/v1/profiles/me", delete(delete_current_profile))
+        .route(
+            "/api/v1/profiles/me/deletion",
+            get(get_current_deletion).delete(cancel_current_deletion),
+        )
+        .route("/api/v1/profiles/me/handle", put(change_current_handle))
+        .route("/api/v1/profiles/me/exports", get(list_exports).post(start_export))
+        .route("/api/v1/profiles/me/exports/:export_id", get(get_export))
+        .route("/api/v1/profiles/me/exports/:export_id/download", get(download_export))
+        .route("/api/v1/identities", get(list_identities).post(link_identity))
+        .route("/api/v1/identities/:provider/:subject", delete(unlink_identity))
+        .route("/api/v1/tokens", get(list_tokens).post(create_token))
+        .route("/api/v1/tokens/:token_id", delete(revoke_token))
+        .route("/api/v1/admin/profiles/:user_id", delete(delete_profile))
+        .route("/api/v1/admin/profiles/:user_id/roles", put(set_profile_roles))
+        .route("/api/v1/admin/profiles/:user_id/deletion", delete(cancel_profile_deletion))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            auth_middleware,
+        ))
+        // Outermost: answer 503 before attempting auth while degraded
+        .route_layer(middleware::from_fn_with_state(
+            app_state.dependencies.clone(),
+            require_dependencies,
+        ));
+
+    // Combine routes
+    Router::new()
+        .merge(public_routes)
+        .merge(public_data_routes)
+        .merge(viewer_routes)
+        .merge(protected_routes)
+        .with_state(app_state)
+        .layer(
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any),
+        )
+}
@@ -1,111 +1,358 @@
-use axum::{extract::{Extension, State}, http::StatusCode, Json};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::auth::middleware::AuthExtension;
+use crate::auth::{AuthUser, ReadProfile, RequireScope, Role, WriteProfile};
 use crate::app_state::AppState;
+use crate::config::ProfilesConfig;
+use crate::db::error::DbError;
+use crate::patch::Patch;
+use crate::repository::{Profile, ProfileUpdate};
+use crate::validation::{TextRule, UrlRule, ValidationErrors, Validator, DISPLAY_NAME};
 
+/// The current user's own view of their stored profile
+///
+/// Answers with the profile's version as its `ETag`.
 #[derive(Serialize, Deserialize)]
 pub struct ProfileResponse {
     pub user_id: String,
-    pub clerk_id: String,
+    pub clerk_id: Option<String>,
+    pub handle: Option<String>,
     pub display_name: Option<String>,
+    pub about_me: Option<String>,
+    pub avatar_url: Option<String>,
+    /// From the session token; emails are not stored
     pub email: Option<String>,
+    pub roles: Vec<Role>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Set while a deletion is pending
+    pub deletion_scheduled_for: Option<DateTime<Utc>>,
+    /// Changes with every write; the `ETag` without its quotes
+    pub version: u64,
 }
 
-/// GET /api/v1/profiles/me - Get current user's profile
-pub async fn get_current_profile(
-    State(_app_state): State<AppState>,
-    Extension(auth): Extension<AuthExtension>,
-) -> Result<Json<ProfileResponse>, StatusCode> {
-    let user = auth.0;
+impl ProfileResponse {
+    pub(crate) fn new(profile: Profile, user: AuthUser) -> Self {
+        Self {
+            user_id: profile.user_id,
+            clerk_id: profile.clerk_id,
+            handle: profile.handle,
+            display_name: profile.display_name,
+            about_me: profile.about_me,
+            avatar_url: profile.avatar_url,
+            email: user.email,
+            roles: profile.roles,
+            created_at: profile.created_at,
+            updated_at: profile.updated_at,
+            deletion_scheduled_for: profile.deletion_scheduled_for,
+            version: profile.version,
+        }
+    }
+}
 
-    Ok(Json(ProfileResponse {
-        user_id: user.user_id,
-        clerk_id: user.clerk_id,
-        display_name: user.display_name,
-        email: user.email,
-    }))
+impl IntoResponse for ProfileResponse {
+    fn into_response(self) -> Response {
+        ([(header::ETAG, etag(self.version))], Json(self)).into_response()
+    }
 }
 
+/// Strong ETag of a profile version
+fn etag(version: u64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Whether an `If-Match` value lets a write to a profile at `version`
+/// through: `*`, or a list holding its ETag. Weak tags never match.
+fn if_match(value: &str, version: u64) -> bool {
+    let current = etag(version);
+    value.split(',').map(str::trim).any(|tag| tag == "*" || tag == current)
+}
+
+fn precondition_failed() -> Response {
+    let body = serde_json::json!({
+        "error": "precondition_failed",
+        "message": "The profile was changed since it was read",
+    });
+    (StatusCode::PRECONDITION_FAILED, Json(body)).into_response()
+}
+
+/// How long CDNs and browsers may reuse a public profile; kept short so
+/// edits and deletions show up quickly
+const PUBLIC_CACHE_CONTROL: &str = "public, max-age=60, stale-while-revalidate=300";
+
+/// What anyone may see of a profile
 #[derive(Serialize, Deserialize)]
-pub struct UpdateProfileRequest {
+pub struct PublicProfileResponse {
+    pub user_id: String,
+    pub handle: Option<String>,
     pub display_name: Option<String>,
     pub about_me: Option<String>,
     pub avatar_url: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct UpdateProfileResponse {
-    pub success: bool,
-    pub message: String,
+impl From<Profile> for PublicProfileResponse {
+    fn from(profile: Profile) -> Self {
+        Self {
+            user_id: profile.user_id,
+            handle: profile.handle,
+            display_name: profile.display_name,
+            about_me: profile.about_me,
+            avatar_url: profile.avatar_url,
+            created_at: profile.created_at,
+        }
+    }
 }
 
-/// PUT /api/v1/profiles/me - Update current user's profile
-pub async fn update_current_profile(
-    State(_app_state): State<AppState>,
-    Extension(auth): Extension<AuthExtension>,
-    Json(payload): Json<UpdateProfileRequest>,
-) -> Result<Json<UpdateProfileResponse>, StatusCode> {
-    let user = auth.0;
+/// A found, not deleted profile as a cacheable public response
+fn public_profile(profile: Option<Profile>) -> Result<impl IntoResponse, StatusCode> {
+    let profile = profile
+        .filter(|profile| !profile.is_deleted())
+        .ok_or(StatusCode::NOT_FOUND)?;
 
-    // Build UPDATE query
-    let mut updates = Vec::new();
+    Ok((
+        [(header::CACHE_CONTROL, PUBLIC_CACHE_CONTROL)],
+        Json(PublicProfileResponse::from(profile)),
+    ))
+}
 
-    if let Some(display_name) = payload.display_name {
-        updates.push(format!("displayName = '{}'", display_name));
-    }
+/// GET /api/v1/profiles/:user_id - Anyone's public profile
+pub async fn get_profile(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let profile = app_state.profiles.find_by_user_id(&user_id).await.map_err(|e| {
+        tracing::error!("Failed to load profile {}: {}", user_id, e);
+        e.status_code()
+    })?;
+
+    public_profile(profile)
+}
 
-    if let Some(about_me) = payload.about_me {
-        updates.push(format!("aboutMe = '{}'", about_me));
+/// GET /api/v1/profiles/by-handle/:handle - Anyone's public profile, by
+/// handle in any case
+///
+/// A recently released handle answers 307 with the profile's current handle
+/// URL.
+pub async fn get_profile_by_handle(
+    State(app_state): State<AppState>,
+    Path(handle): Path<String>,
+) -> Result<Response, StatusCode> {
+    let profile = app_state.profiles.find_by_handle(&handle).await.map_err(|e| {
+        tracing::error!("Failed to load profile by handle {}: {}", handle, e);
+        e.status_code()
+    })?;
+    if profile.is_some() {
+        return public_profile(profile).map(IntoResponse::into_response);
     }
 
-    if let Some(avatar_url) = payload.avatar_url {
-        updates.push(format!("avatarUrl = '{}'", avatar_url));
+    let released = app_state
+        .handle_history
+        .find_active(&handle, Utc::now())
+        .await
+        .map_err(|e| e.status_code())?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let current = app_state
+        .profiles
+        .find_by_user_id(&released.user_id)
+        .await
+        .map_err(|e| e.status_code())?
+        .filter(|profile| !profile.is_deleted())
+        .and_then(|profile| profile.handle)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok((
+        StatusCode::TEMPORARY_REDIRECT,
+        [
+            (header::LOCATION, format!("/api/v1/profiles/by-handle/{}", current)),
+            (header::CACHE_CONTROL, PUBLIC_CACHE_CONTROL.to_string()),
+        ],
+    )
+        .into_response())
+}
+
+/// The stored profile of the authenticated user
+pub(crate) async fn load_profile(app_state: &AppState, user_id: &str) -> Result<Profile, StatusCode> {
+    app_state
+        .profiles
+        .find_by_user_id(user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load profile {}: {}", user_id, e);
+            e.status_code()
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// GET /api/v1/profiles/me - Get current user's profile
+pub async fn get_current_profile(
+    State(app_state): State<AppState>,
+    RequireScope(user, ..): RequireScope<ReadProfile>,
+) -> Result<ProfileResponse, StatusCode> {
+    let profile = load_profile(&app_state, &user.user_id).await?;
+    Ok(ProfileResponse::new(profile, user))
+}
+
+/// Body of PUT: the complete set of editable fields
+///
+/// A missing or null `about_me` or `avatar_url` clears it; `display_name` is
+/// required.
+#[derive(Serialize, Deserialize)]
+pub struct ReplaceProfileRequest {
+    pub display_name: Option<String>,
+    pub about_me: Option<String>,
+    pub avatar_url: Option<String>,
+    /// Expected profile version, for clients that can't send `If-Match`
+    pub version: Option<u64>,
+}
+
+impl From<ReplaceProfileRequest> for PatchProfileRequest {
+    fn from(request: ReplaceProfileRequest) -> Self {
+        Self {
+            display_name: request.display_name.into(),
+            about_me: request.about_me.into(),
+            avatar_url: request.avatar_url.into(),
+            version: request.version,
+        }
     }
+}
+
+/// Body of PATCH, a JSON Merge Patch: absent fields are left alone and
+/// `null` clears one
+#[derive(Deserialize)]
+pub struct PatchProfileRequest {
+    #[serde(default)]
+    pub display_name: Patch<String>,
+    #[serde(default)]
+    pub about_me: Patch<String>,
+    #[serde(default)]
+    pub avatar_url: Patch<String>,
+    /// Expected profile version, for clients that can't send `If-Match`
+    pub version: Option<u64>,
+}
 
-    if updates.is_empty() {
-        return Ok(Json(UpdateProfileResponse {
-            success: false,
-            message: "No fields to update".to_string(),
-        }));
+const ABOUT_ME: TextRule = TextRule {
+    min_chars: 0,
+    max_chars: 1000,
+    multiline: true,
+};
+const AVATAR_URL_MAX_LEN: usize = 2048;
+
+impl PatchProfileRequest {
+    /// Normalize and check the fields that are set
+    fn validate(self, config: &ProfilesConfig) -> Result<ProfileUpdate, ValidationErrors> {
+        let mut validator = Validator::new();
+        let avatar_rule = UrlRule {
+            max_len: AVATAR_URL_MAX_LEN,
+            allowed_hosts: &config.avatar_hosts,
+        };
+
+        // Every profile keeps a display name
+        if self.display_name == Patch::Null {
+            validator.error("display_name", "required", "must be set");
+        }
+
+        let update = ProfileUpdate {
+            display_name: self
+                .display_name
+                .map(|value| validator.text("display_name", &value, DISPLAY_NAME)),
+            about_me: self.about_me.map(|value| validator.text("about_me", &value, ABOUT_ME)),
+            avatar_url: self
+                .avatar_url
+                .map(|value| validator.url("avatar_url", &value, avatar_rule)),
+        };
+
+        validator.finish()?;
+        Ok(update)
     }
+}
 
-    updates.push("updatedAt = sysdate()".to_string());
-
-    let query = format!(
-        "UPDATE Profile SET {} WHERE userId = '{}'",
-        updates.join(", "),
-        user.user_id
-    );
-
-    // Execute via HTTP API
-    let db_host = std::env::var("ARCADE_DB_HOST").unwrap_or_else(|_| "localhost".to_string());
-    let db_port = std::env::var("ARCADE_DB_PORT").unwrap_or_else(|_| "2480".to_string());
-    let db_user = std::env::var("ARCADE_DB_USER").unwrap_or_else(|_| "root".to_string());
-    let db_password = std::env::var("ARCADE_DB_PASSWORD").unwrap_or_else(|_| "".to_string());
-    let db_name = std::env::var("ARCADE_DB_NAME").unwrap_or_else(|_| "cynnycty".to_string());
-
-    let url = format!("http://{}:{}/api/v1/command/{}", db_host, db_port, db_name);
-    let payload = serde_json::json!({
-        "language": "sql",
-        "command": query
-    });
+/// PUT /api/v1/profiles/me - Replace the current user's editable profile
+/// fields and return the profile
+///
+/// 412 if `If-Match` or the body's `version` no longer matches the profile;
+/// 422 lists every invalid field.
+pub async fn update_current_profile(
+    State(app_state): State<AppState>,
+    RequireScope(user, ..): RequireScope<WriteProfile>,
+    headers: HeaderMap,
+    Json(payload): Json<ReplaceProfileRequest>,
+) -> Result<ProfileResponse, Response> {
+    apply_update(&app_state, user, &headers, PatchProfileRequest::from(payload)).await
+}
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&url)
-        .basic_auth(&db_user, Some(&db_password))
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+/// PATCH /api/v1/profiles/me - Merge-patch the current user's profile and
+/// return it
+///
+/// Accepts `application/merge-patch+json` as well as `application/json`.
+/// 412 if `If-Match` or the body's `version` no longer matches the profile;
+/// 422 lists every invalid field.
+pub async fn patch_current_profile(
+    State(app_state): State<AppState>,
+    RequireScope(user, ..): RequireScope<WriteProfile>,
+    headers: HeaderMap,
+    Json(payload): Json<PatchProfileRequest>,
+) -> Result<ProfileResponse, Response> {
+    apply_update(&app_state, user, &headers, payload).await
+}
 
-    if response.status().is_success() {
-        Ok(Json(UpdateProfileResponse {
-            success: true,
-            message: "Profile updated successfully".to_string(),
-        }))
+async fn apply_update(
+    app_state: &AppState,
+    user: AuthUser,
+    headers: &HeaderMap,
+    request: PatchProfileRequest,
+) -> Result<ProfileResponse, Response> {
+    // Preconditions are checked before the body, against the stored profile;
+    // the update itself then only applies at the version that was checked
+    let if_match_header = headers
+        .get(header::IF_MATCH)
+        .map(|value| value.to_str().unwrap_or_default());
+    let body_version = request.version;
+    let current = if if_match_header.is_some() || body_version.is_some() {
+        let profile = load_profile(app_state, &user.user_id)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let matches = if_match_header.is_none_or(|value| if_match(value, profile.version))
+            && body_version.is_none_or(|version| version == profile.version);
+        if !matches {
+            return Err(precondition_failed());
+        }
+        Some(profile)
     } else {
-        Err(StatusCode::INTERNAL_SERVER_ERROR)
+        None
+    };
+
+    let update = request
+        .validate(&app_state.config.profiles)
+        .map_err(IntoResponse::into_response)?;
+
+    // Nothing to change; answer with the profile as it is
+    if update.is_empty() {
+        let profile = match current {
+            Some(profile) => profile,
+            None => load_profile(app_state, &user.user_id)
+                .await
+                .map_err(IntoResponse::into_response)?,
+        };
+        return Ok(ProfileResponse::new(profile, user));
     }
+
+    let expected_version = current.map(|profile| profile.version);
+    let profile = match app_state.profiles.update(&user.user_id, update, expected_version).await {
+        Ok(profile) => profile,
+        // Another write got in after the precondition check
+        Err(DbError::Conflict(_)) => return Err(precondition_failed()),
+        Err(e) => {
+            tracing::error!("Failed to update profile: {}", e);
+            return Err(e.status_code().into_response());
+        }
+    };
+
+    Ok(ProfileResponse::new(profile, user))
 }
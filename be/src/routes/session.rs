@@ -0,0 +1,36 @@
+use axum::{extract::Extension, Json};
+use serde::Serialize;
+
+use crate::auth::middleware::OptionalAuthExtension;
+use crate::auth::Role;
+
+#[derive(Serialize)]
+pub struct SessionResponse {
+    pub authenticated: bool,
+    pub user_id: Option<String>,
+    pub display_name: Option<String>,
+    pub roles: Vec<Role>,
+}
+
+/// GET /api/v1/session - Who is viewing, if anyone
+///
+/// Answers 200 for anonymous requests so the frontend can decide what to
+/// render without treating "not signed in" as an error.
+pub async fn get_session(
+    Extension(OptionalAuthExtension(viewer)): Extension<OptionalAuthExtension>,
+) -> Json<SessionResponse> {
+    Json(match viewer {
+        Some(user) => SessionResponse {
+            authenticated: true,
+            user_id: Some(user.user_id),
+            display_name: user.display_name,
+            roles: user.roles,
+        },
+        None => SessionResponse {
+            authenticated: false,
+            user_id: None,
+            display_name: None,
+            roles: Vec::new(),
+        },
+    })
+}
@@ -0,0 +1,163 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::AppState;
+use crate::auth::api_token;
+use crate::auth::{RequireSession, Scope};
+use crate::repository::{ApiToken, NewApiToken};
+use crate::validation::{TextRule, Validator};
+
+/// Longest lifetime a token may be created with
+const MAX_EXPIRES_IN_DAYS: u32 = 365;
+const NAME: TextRule = TextRule {
+    min_chars: 1,
+    max_chars: 100,
+    multiline: false,
+};
+
+#[derive(Deserialize)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Never expires if omitted
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct ApiTokenResponse {
+    pub id: String,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiToken> for ApiTokenResponse {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            id: token.token_id,
+            name: token.name,
+            prefix: token.prefix,
+            scopes: token.scopes,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+            revoked_at: token.revoked_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CreateTokenResponse {
+    /// The plaintext token; it cannot be retrieved again
+    pub token: String,
+    #[serde(flatten)]
+    pub details: ApiTokenResponse,
+}
+
+/// POST /api/v1/tokens - Create an API token for the current user
+///
+/// 422 lists every invalid field.
+pub async fn create_token(
+    State(app_state): State<AppState>,
+    RequireSession(user): RequireSession,
+    Json(payload): Json<CreateTokenRequest>,
+) -> Result<(StatusCode, Json<CreateTokenResponse>), Response> {
+    let mut validator = Validator::new();
+    let name = validator.text("name", &payload.name, NAME);
+    if payload.scopes.is_empty() {
+        validator.error("scopes", "required", "must list at least one scope");
+    }
+    if let Some(days) = payload.expires_in_days
+        && (days == 0 || days > MAX_EXPIRES_IN_DAYS)
+    {
+        validator.error(
+            "expires_in_days",
+            "out_of_range",
+            format!("must be between 1 and {}", MAX_EXPIRES_IN_DAYS),
+        );
+    }
+    validator.finish().map_err(IntoResponse::into_response)?;
+
+    let expires_at = payload
+        .expires_in_days
+        .map(|days| Utc::now() + Duration::days(days.into()));
+
+    let mut scopes = payload.scopes;
+    scopes.sort();
+    scopes.dedup();
+
+    let generated = api_token::generate();
+    let token = app_state
+        .api_tokens
+        .create(NewApiToken {
+            token_id: uuid::Uuid::new_v4().to_string(),
+            user_id: user.user_id.clone(),
+            name,
+            token_hash: generated.hash,
+            prefix: generated.prefix,
+            scopes,
+            expires_at,
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create API token: {}", e);
+            e.status_code().into_response()
+        })?;
+
+    tracing::info!("User {} created API token {}", user.user_id, token.token_id);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateTokenResponse {
+            token: generated.plaintext,
+            details: token.into(),
+        }),
+    ))
+}
+
+/// GET /api/v1/tokens - List the current user's API tokens
+pub async fn list_tokens(
+    State(app_state): State<AppState>,
+    RequireSession(user): RequireSession,
+) -> Result<Json<Vec<ApiTokenResponse>>, StatusCode> {
+    let tokens = app_state
+        .api_tokens
+        .list_for_user(&user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list API tokens: {}", e);
+            e.status_code()
+        })?;
+
+    Ok(Json(tokens.into_iter().map(ApiTokenResponse::from).collect()))
+}
+
+/// DELETE /api/v1/tokens/:token_id - Revoke one of the current user's API tokens
+pub async fn revoke_token(
+    State(app_state): State<AppState>,
+    RequireSession(user): RequireSession,
+    Path(token_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    app_state
+        .api_tokens
+        .revoke(&user.user_id, &token_id)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to revoke API token {}: {}", token_id, e);
+            e.status_code()
+        })?;
+
+    tracing::info!("User {} revoked API token {}", user.user_id, token_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,58 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+
+use crate::app_state::AppState;
+use crate::webhooks::clerk::{handle_event, ClerkEvent, WebhookError};
+use crate::webhooks::svix::SvixVerifier;
+
+/// POST /api/v1/webhooks/clerk - Clerk user events, delivered by Svix
+///
+/// Any non-2xx answer makes Svix retry the delivery later.
+pub async fn clerk_webhook(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    // Only routed when a webhook secret is configured, and the secret was
+    // validated at startup
+    let Some(secret) = app_state.config.clerk.webhook_secret.as_ref() else {
+        return StatusCode::NOT_FOUND;
+    };
+    let verifier = match SvixVerifier::new(secret.expose()) {
+        Ok(verifier) => verifier,
+        Err(e) => {
+            tracing::error!("Invalid Clerk webhook secret: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    if let Err(e) = verifier.verify(&headers, &body) {
+        tracing::warn!("Rejected Clerk webhook: {}", e);
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let event: ClerkEvent = match serde_json::from_slice(&body) {
+        Ok(event) => event,
+        Err(e) => {
+            tracing::warn!("Malformed Clerk webhook: {}", e);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    tracing::debug!("Clerk webhook: {}", event.event_type);
+
+    match handle_event(&app_state, event).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(WebhookError::Payload(e)) => {
+            tracing::warn!("Malformed Clerk webhook data: {}", e);
+            StatusCode::BAD_REQUEST
+        }
+        Err(WebhookError::Db(e)) => {
+            tracing::error!("Failed to apply Clerk webhook: {}", e);
+            e.status_code()
+        }
+    }
+}
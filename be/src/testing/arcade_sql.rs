@@ -0,0 +1,638 @@
+//! Parser for the subset of ArcadeDB SQL used by the backend
+//!
+//! Covers schema DDL (`CREATE TYPE/PROPERTY/INDEX`), `SELECT` with simple
+//! projections, `WHERE`, `ORDER BY` and `LIMIT`, and `INSERT ... SET`,
+//! `UPDATE ... SET/REMOVE` and `DELETE`. Anything else is rejected with a
+//! parse error, so a test fails loudly instead of silently passing against
+//! semantics the stub doesn't implement.
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Param(String),
+    Str(String),
+    Num(Value),
+    Sym(&'static str),
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Literal(Value),
+    Param(String),
+    Field(String),
+    SysDate,
+    CountAll,
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Compare(Expr, CompareOp, Expr),
+    IsNull(Expr, bool),
+    In(Expr, Vec<Expr>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Projection {
+    pub expr: Expr,
+    pub alias: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexDefinition {
+    pub name: Option<String>,
+    pub type_name: String,
+    pub properties: Vec<String>,
+    pub unique: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Statement {
+    CreateType {
+        name: String,
+        if_not_exists: bool,
+    },
+    CreateProperty {
+        type_name: String,
+        property: String,
+        if_not_exists: bool,
+    },
+    CreateIndex {
+        index: IndexDefinition,
+        if_not_exists: bool,
+    },
+    Select {
+        projections: Vec<Projection>,
+        from: Option<String>,
+        filter: Option<Condition>,
+        order_by: Vec<(String, bool)>,
+        limit: Option<usize>,
+    },
+    Insert {
+        type_name: String,
+        assignments: Vec<(String, Expr)>,
+    },
+    Update {
+        type_name: String,
+        assignments: Vec<(String, Expr)>,
+        removals: Vec<String>,
+        filter: Option<Condition>,
+    },
+    Delete {
+        type_name: String,
+        filter: Option<Condition>,
+    },
+}
+
+/// Parse one SQL statement
+pub fn parse(sql: &str) -> Result<Statement, String> {
+    let tokens = tokenize(sql)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let statement = parser.statement()?;
+    parser.eat_sym(";");
+
+    match parser.peek() {
+        None => Ok(statement),
+        Some(token) => Err(format!("Unexpected {:?} after end of statement", token)),
+    }
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    let ident_char = |c: char| c.is_alphanumeric() || c == '_' || c == '@';
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '\'' || c == '"' {
+            let quote = c;
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("Unterminated string literal".to_string()),
+                    Some('\\') => {
+                        if let Some(&escaped) = chars.get(i + 1) {
+                            text.push(escaped);
+                        }
+                        i += 2;
+                    }
+                    Some(&ch) if ch == quote => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&ch) => {
+                        text.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Str(text));
+        } else if c == '`' {
+            let start = i + 1;
+            let end = chars[start..]
+                .iter()
+                .position(|&ch| ch == '`')
+                .ok_or("Unterminated quoted identifier")?;
+            tokens.push(Token::Ident(chars[start..start + end].iter().collect()));
+            i = start + end + 1;
+        } else if c == ':' && chars.get(i + 1).is_some_and(|&ch| ident_char(ch)) {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && ident_char(chars[i]) {
+                i += 1;
+            }
+            tokens.push(Token::Param(chars[start..i].iter().collect()));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = match text.parse::<i64>() {
+                Ok(int) => Value::from(int),
+                Err(_) => Value::from(text.parse::<f64>().map_err(|e| e.to_string())?),
+            };
+            tokens.push(Token::Num(value));
+        } else if ident_char(c) {
+            let start = i;
+            while i < chars.len() && ident_char(chars[i]) {
+                i += 1;
+            }
+            let mut ident: String = chars[start..i].iter().collect();
+            // `schema:types` and friends are a single identifier
+            if ident.eq_ignore_ascii_case("schema") && chars.get(i) == Some(&':') {
+                let rest_start = i + 1;
+                i = rest_start;
+                while i < chars.len() && ident_char(chars[i]) {
+                    i += 1;
+                }
+                ident = format!("schema:{}", chars[rest_start..i].iter().collect::<String>());
+            }
+            tokens.push(Token::Ident(ident));
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let symbol = match two.as_str() {
+                "<=" => Some("<="),
+                ">=" => Some(">="),
+                "<>" => Some("<>"),
+                "!=" => Some("!="),
+                _ => None,
+            };
+            if let Some(symbol) = symbol {
+                tokens.push(Token::Sym(symbol));
+                i += 2;
+                continue;
+            }
+
+            let symbol = match c {
+                '=' => "=",
+                '<' => "<",
+                '>' => ">",
+                ',' => ",",
+                '(' => "(",
+                ')' => ")",
+                '.' => ".",
+                '*' => "*",
+                '+' => "+",
+                '-' => "-",
+                ';' => ";",
+                other => return Err(format!("Unexpected character '{}'", other)),
+            };
+            tokens.push(Token::Sym(symbol));
+            i += 1;
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_kw(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_kw(&mut self, keyword: &str) -> bool {
+        if self.peek_kw(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_kw(&mut self, keyword: &str) -> Result<(), String> {
+        if self.eat_kw(keyword) {
+            Ok(())
+        } else {
+            Err(format!("Expected {} but found {:?}", keyword, self.peek()))
+        }
+    }
+
+    fn eat_sym(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Sym(sym)) if *sym == symbol) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_sym(&mut self, symbol: &str) -> Result<(), String> {
+        if self.eat_sym(symbol) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' but found {:?}", symbol, self.peek()))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Ident(ident)) => Ok(ident),
+            other => Err(format!("Expected identifier but found {:?}", other)),
+        }
+    }
+
+    fn if_not_exists(&mut self) -> Result<bool, String> {
+        if self.eat_kw("IF") {
+            self.expect_kw("NOT")?;
+            self.expect_kw("EXISTS")?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn statement(&mut self) -> Result<Statement, String> {
+        if self.eat_kw("CREATE") {
+            self.create()
+        } else if self.eat_kw("SELECT") {
+            self.select()
+        } else if self.eat_kw("INSERT") {
+            self.insert()
+        } else if self.eat_kw("UPDATE") {
+            self.update()
+        } else if self.eat_kw("DELETE") {
+            self.delete()
+        } else {
+            Err(format!("Unsupported statement starting with {:?}", self.peek()))
+        }
+    }
+
+    fn create(&mut self) -> Result<Statement, String> {
+        if self.eat_kw("DOCUMENT") || self.eat_kw("VERTEX") || self.eat_kw("EDGE") {
+            self.expect_kw("TYPE")?;
+            let name = self.ident()?;
+            let if_not_exists = self.if_not_exists()?;
+            return Ok(Statement::CreateType {
+                name,
+                if_not_exists,
+            });
+        }
+
+        if self.eat_kw("PROPERTY") {
+            let type_name = self.ident()?;
+            self.expect_sym(".")?;
+            let property = self.ident()?;
+            let if_not_exists = self.if_not_exists()?;
+            // Property type, e.g. STRING or LIST OF STRING
+            self.ident()?;
+            if self.eat_kw("OF") {
+                self.ident()?;
+            }
+            return Ok(Statement::CreateProperty {
+                type_name,
+                property,
+                if_not_exists,
+            });
+        }
+
+        if self.eat_kw("INDEX") {
+            let name = if self.peek_kw("IF") || self.peek_kw("ON") {
+                None
+            } else {
+                Some(self.ident()?)
+            };
+            let if_not_exists = self.if_not_exists()?;
+            self.expect_kw("ON")?;
+            let type_name = self.ident()?;
+            self.expect_sym("(")?;
+            let mut properties = vec![self.ident()?];
+            while self.eat_sym(",") {
+                properties.push(self.ident()?);
+            }
+            self.expect_sym(")")?;
+            let unique = match self.ident()?.to_ascii_uppercase().as_str() {
+                "UNIQUE" => true,
+                "NOTUNIQUE" | "FULL_TEXT" => false,
+                other => return Err(format!("Unsupported index type {}", other)),
+            };
+            return Ok(Statement::CreateIndex {
+                index: IndexDefinition {
+                    name,
+                    type_name,
+                    properties,
+                    unique,
+                },
+                if_not_exists,
+            });
+        }
+
+        Err(format!("Unsupported CREATE {:?}", self.peek()))
+    }
+
+    fn select(&mut self) -> Result<Statement, String> {
+        let mut projections = Vec::new();
+
+        if !self.eat_sym("*") && !self.peek_kw("FROM") {
+            loop {
+                let expr = self.expr()?;
+                let alias = if self.eat_kw("AS") {
+                    self.ident()?
+                } else {
+                    match &expr {
+                        Expr::Field(field) => field.clone(),
+                        Expr::CountAll => "count(*)".to_string(),
+                        other => format!("{:?}", other),
+                    }
+                };
+                projections.push(Projection { expr, alias });
+                if !self.eat_sym(",") {
+                    break;
+                }
+            }
+        }
+
+        let from = if self.eat_kw("FROM") {
+            Some(self.ident()?)
+        } else {
+            None
+        };
+
+        let filter = self.where_clause()?;
+
+        let mut order_by = Vec::new();
+        if self.eat_kw("ORDER") {
+            self.expect_kw("BY")?;
+            loop {
+                let field = self.ident()?;
+                let descending = if self.eat_kw("DESC") {
+                    true
+                } else {
+                    self.eat_kw("ASC");
+                    false
+                };
+                order_by.push((field, descending));
+                if !self.eat_sym(",") {
+                    break;
+                }
+            }
+        }
+
+        let limit = self.limit()?;
+
+        Ok(Statement::Select {
+            projections,
+            from,
+            filter,
+            order_by,
+            limit,
+        })
+    }
+
+    fn insert(&mut self) -> Result<Statement, String> {
+        self.expect_kw("INTO")?;
+        let type_name = self.ident()?;
+        self.expect_kw("SET")?;
+        let assignments = self.assignments()?;
+
+        Ok(Statement::Insert {
+            type_name,
+            assignments,
+        })
+    }
+
+    fn update(&mut self) -> Result<Statement, String> {
+        let type_name = self.ident()?;
+        let mut assignments = Vec::new();
+        let mut removals = Vec::new();
+
+        loop {
+            if self.eat_kw("SET") {
+                assignments.extend(self.assignments()?);
+            } else if self.eat_kw("REMOVE") {
+                removals.push(self.ident()?);
+                while self.eat_sym(",") {
+                    removals.push(self.ident()?);
+                }
+            } else {
+                break;
+            }
+        }
+
+        if assignments.is_empty() && removals.is_empty() {
+            return Err("UPDATE requires SET or REMOVE".to_string());
+        }
+
+        let filter = self.where_clause()?;
+
+        Ok(Statement::Update {
+            type_name,
+            assignments,
+            removals,
+            filter,
+        })
+    }
+
+    fn delete(&mut self) -> Result<Statement, String> {
+        self.expect_kw("FROM")?;
+        let type_name = self.ident()?;
+        let filter = self.where_clause()?;
+
+        Ok(Statement::Delete { type_name, filter })
+    }
+
+    fn assignments(&mut self) -> Result<Vec<(String, Expr)>, String> {
+        let mut assignments = Vec::new();
+        loop {
+            let field = self.ident()?;
+            self.expect_sym("=")?;
+            assignments.push((field, self.expr()?));
+            if !self.eat_sym(",") {
+                break;
+            }
+        }
+        Ok(assignments)
+    }
+
+    fn where_clause(&mut self) -> Result<Option<Condition>, String> {
+        if self.eat_kw("WHERE") {
+            Ok(Some(self.or_condition()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn limit(&mut self) -> Result<Option<usize>, String> {
+        if !self.eat_kw("LIMIT") {
+            return Ok(None);
+        }
+        match self.next() {
+            Some(Token::Num(Value::Number(n))) if n.is_u64() => Ok(n.as_u64().map(|n| n as usize)),
+            other => Err(format!("Expected LIMIT count but found {:?}", other)),
+        }
+    }
+
+    fn or_condition(&mut self) -> Result<Condition, String> {
+        let mut condition = self.and_condition()?;
+        while self.eat_kw("OR") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and_condition()?));
+        }
+        Ok(condition)
+    }
+
+    fn and_condition(&mut self) -> Result<Condition, String> {
+        let mut condition = self.not_condition()?;
+        while self.eat_kw("AND") {
+            condition = Condition::And(Box::new(condition), Box::new(self.not_condition()?));
+        }
+        Ok(condition)
+    }
+
+    fn not_condition(&mut self) -> Result<Condition, String> {
+        if self.eat_kw("NOT") {
+            return Ok(Condition::Not(Box::new(self.not_condition()?)));
+        }
+
+        // Parenthesized condition, unless it turns out to be an expression
+        if matches!(self.peek(), Some(Token::Sym("("))) {
+            let checkpoint = self.pos;
+            self.pos += 1;
+            if let Ok(condition) = self.or_condition()
+                && self.eat_sym(")")
+            {
+                return Ok(condition);
+            }
+            self.pos = checkpoint;
+        }
+
+        let left = self.expr()?;
+
+        if self.eat_kw("IS") {
+            let negated = self.eat_kw("NOT");
+            self.expect_kw("NULL")?;
+            return Ok(Condition::IsNull(left, !negated));
+        }
+
+        if self.eat_kw("IN") {
+            let mut values = Vec::new();
+            if self.eat_sym("(") {
+                values.push(self.expr()?);
+                while self.eat_sym(",") {
+                    values.push(self.expr()?);
+                }
+                self.expect_sym(")")?;
+            } else {
+                values.push(self.expr()?);
+            }
+            return Ok(Condition::In(left, values));
+        }
+
+        let op = match self.next() {
+            Some(Token::Sym("=")) => CompareOp::Eq,
+            Some(Token::Sym("<>")) | Some(Token::Sym("!=")) => CompareOp::Ne,
+            Some(Token::Sym("<")) => CompareOp::Lt,
+            Some(Token::Sym("<=")) => CompareOp::Le,
+            Some(Token::Sym(">")) => CompareOp::Gt,
+            Some(Token::Sym(">=")) => CompareOp::Ge,
+            other => return Err(format!("Expected comparison but found {:?}", other)),
+        };
+
+        Ok(Condition::Compare(left, op, self.expr()?))
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut expr = self.term()?;
+        loop {
+            if self.eat_sym("+") {
+                expr = Expr::Add(Box::new(expr), Box::new(self.term()?));
+            } else if self.eat_sym("-") {
+                expr = Expr::Sub(Box::new(expr), Box::new(self.term()?));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Str(text)) => Ok(Expr::Literal(Value::String(text))),
+            Some(Token::Num(value)) => Ok(Expr::Literal(value)),
+            Some(Token::Param(name)) => Ok(Expr::Param(name)),
+            Some(Token::Sym("(")) => {
+                let expr = self.expr()?;
+                self.expect_sym(")")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(ident)) => {
+                if self.eat_sym("(") {
+                    return match ident.to_ascii_lowercase().as_str() {
+                        "sysdate" => {
+                            self.expect_sym(")")?;
+                            Ok(Expr::SysDate)
+                        }
+                        "count" => {
+                            self.expect_sym("*")?;
+                            self.expect_sym(")")?;
+                            Ok(Expr::CountAll)
+                        }
+                        other => Err(format!("Unsupported function {}()", other)),
+                    };
+                }
+                match ident.to_ascii_lowercase().as_str() {
+                    "null" => Ok(Expr::Literal(Value::Null)),
+                    "true" => Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => Ok(Expr::Literal(Value::Bool(false))),
+                    _ => Ok(Expr::Field(ident)),
+                }
+            }
+            other => Err(format!("Expected expression but found {:?}", other)),
+        }
+    }
+}
@@ -0,0 +1,669 @@
+//! In-process stand-in for the ArcadeDB HTTP API
+//!
+//! Serves `/api/v1/ready`, `/api/v1/query/{db}` and `/api/v1/command/{db}`
+//! on a random local port and keeps every database in memory. Statements
+//! are interpreted by [`arcade_sql`](super::arcade_sql); errors mirror the
+//! bodies ArcadeDB sends, so `DbError` classification behaves the same as
+//! against a real server.
+//!
+//! ```ignore
+//! let stub = ArcadeStub::start().await;
+//! let db = stub.connection("cynnycty");
+//! run_migrations(&db, &config.database.migrations_dir).await?;
+//! ```
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::db::connection::DatabaseConnection;
+use crate::testing::arcade_sql::{
+    self, CompareOp, Condition, Expr, IndexDefinition, Projection, Statement,
+};
+
+/// A running stub server; shut down when dropped
+pub struct ArcadeStub {
+    addr: SocketAddr,
+    databases: Arc<Mutex<HashMap<String, StubDatabase>>>,
+    server: JoinHandle<()>,
+}
+
+impl ArcadeStub {
+    /// Start a stub on `127.0.0.1` with an OS-assigned port
+    pub async fn start() -> Self {
+        let databases: Arc<Mutex<HashMap<String, StubDatabase>>> = Arc::default();
+
+        let app = Router::new()
+            .route("/api/v1/ready", get(|| async { StatusCode::NO_CONTENT }))
+            .route("/api/v1/query/:database", post(handle_query))
+            .route("/api/v1/command/:database", post(handle_command))
+            .with_state(databases.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind ArcadeDB stub");
+        let addr = listener.local_addr().expect("stub has no local address");
+
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app).await.ok();
+        });
+
+        Self {
+            addr,
+            databases,
+            server,
+        }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// A `DatabaseConnection` pointed at this stub
+    pub fn connection(&self, database_name: &str) -> DatabaseConnection {
+        DatabaseConnection::new("127.0.0.1", self.port(), "root", "", database_name)
+            .expect("failed to build stub connection")
+    }
+
+    /// Every record of `type_name` currently stored in `database_name`
+    pub fn records(&self, database_name: &str, type_name: &str) -> Vec<Value> {
+        self.databases
+            .lock()
+            .unwrap()
+            .get(database_name)
+            .and_then(|db| db.records.get(type_name))
+            .map(|records| records.iter().cloned().map(Value::Object).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for ArcadeStub {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+#[derive(Deserialize)]
+struct CommandRequest {
+    #[serde(default = "default_language")]
+    language: String,
+    command: String,
+    #[serde(default)]
+    params: Map<String, Value>,
+}
+
+fn default_language() -> String {
+    "sql".to_string()
+}
+
+type SharedDatabases = Arc<Mutex<HashMap<String, StubDatabase>>>;
+
+async fn handle_query(
+    State(databases): State<SharedDatabases>,
+    Path(database): Path<String>,
+    Json(request): Json<CommandRequest>,
+) -> Response {
+    execute(&databases, &database, request, true)
+}
+
+async fn handle_command(
+    State(databases): State<SharedDatabases>,
+    Path(database): Path<String>,
+    Json(request): Json<CommandRequest>,
+) -> Response {
+    execute(&databases, &database, request, false)
+}
+
+fn execute(
+    databases: &SharedDatabases,
+    database: &str,
+    request: CommandRequest,
+    read_only: bool,
+) -> Response {
+    if !request.language.eq_ignore_ascii_case("sql") {
+        return StubError::parse(format!("Language '{}' is not supported", request.language))
+            .into_response();
+    }
+
+    let statement = match arcade_sql::parse(&request.command) {
+        Ok(statement) => statement,
+        Err(e) => return StubError::parse(e).into_response(),
+    };
+
+    if read_only && !matches!(statement, Statement::Select { .. }) {
+        return StubError::parse("Query is not idempotent".to_string()).into_response();
+    }
+
+    let mut databases = databases.lock().unwrap();
+    let db = databases.entry(database.to_string()).or_default();
+
+    match db.execute(statement, &request.params) {
+        Ok(result) => Json(json!({
+            "user": "root",
+            "version": "stub",
+            "result": result,
+        }))
+        .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// An error response shaped like ArcadeDB's
+struct StubError {
+    status: StatusCode,
+    error: String,
+    detail: String,
+    exception: &'static str,
+}
+
+impl StubError {
+    fn parse(detail: String) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error: "Error on parsing command".to_string(),
+            detail,
+            exception: "com.arcadedb.exception.CommandParsingException",
+        }
+    }
+
+    fn schema(detail: String) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: "Error on command execution (PostCommandHandler)".to_string(),
+            detail,
+            exception: "com.arcadedb.exception.SchemaException",
+        }
+    }
+
+    fn duplicate_key(index: &str, keys: &[Value], rid: &str) -> Self {
+        let keys: Vec<String> = keys.iter().map(render).collect();
+        Self {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            error: "Found duplicate key in index".to_string(),
+            detail: format!("{}|[{}]|{}", index, keys.join(", "), rid),
+            exception: "com.arcadedb.exception.DuplicatedKeyException",
+        }
+    }
+}
+
+impl IntoResponse for StubError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(json!({
+                "error": self.error,
+                "detail": self.detail,
+                "exception": self.exception,
+            })),
+        )
+            .into_response()
+    }
+}
+
+fn render(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Default)]
+struct StubType {
+    properties: Vec<String>,
+    indexes: Vec<StubIndex>,
+}
+
+struct StubIndex {
+    name: String,
+    properties: Vec<String>,
+    unique: bool,
+}
+
+#[derive(Default)]
+struct StubDatabase {
+    types: BTreeMap<String, StubType>,
+    records: HashMap<String, Vec<Map<String, Value>>>,
+    next_position: u64,
+}
+
+impl StubDatabase {
+    fn execute(
+        &mut self,
+        statement: Statement,
+        params: &Map<String, Value>,
+    ) -> Result<Vec<Value>, StubError> {
+        match statement {
+            Statement::CreateType {
+                name,
+                if_not_exists,
+            } => {
+                if self.types.contains_key(&name) {
+                    if if_not_exists {
+                        return Ok(vec![]);
+                    }
+                    return Err(StubError::schema(format!("Type '{}' already exists", name)));
+                }
+                self.types.insert(name.clone(), StubType::default());
+                Ok(vec![json!({ "operation": "create document type", "typeName": name })])
+            }
+            Statement::CreateProperty {
+                type_name,
+                property,
+                if_not_exists,
+            } => {
+                let stub_type = self.type_mut(&type_name)?;
+                if stub_type.properties.contains(&property) {
+                    if if_not_exists {
+                        return Ok(vec![]);
+                    }
+                    return Err(StubError::schema(format!(
+                        "Property '{}' already exists in type '{}'",
+                        property, type_name
+                    )));
+                }
+                stub_type.properties.push(property.clone());
+                Ok(vec![json!({ "operation": "create property", "typeName": type_name, "propertyName": property })])
+            }
+            Statement::CreateIndex {
+                index,
+                if_not_exists,
+            } => self.create_index(index, if_not_exists),
+            Statement::Select {
+                projections,
+                from,
+                filter,
+                order_by,
+                limit,
+            } => self.select(projections, from, filter, order_by, limit, params),
+            Statement::Insert {
+                type_name,
+                assignments,
+            } => self.insert(type_name, assignments, params),
+            Statement::Update {
+                type_name,
+                assignments,
+                removals,
+                filter,
+            } => self.update(type_name, assignments, removals, filter, params),
+            Statement::Delete { type_name, filter } => {
+                self.type_mut(&type_name)?;
+                let records = self.records.entry(type_name).or_default();
+                let before = records.len();
+                let mut failure = None;
+                records.retain(|record| match matches(filter.as_ref(), record, params) {
+                    Ok(matched) => !matched,
+                    Err(e) => {
+                        failure.get_or_insert(e);
+                        true
+                    }
+                });
+                if let Some(e) = failure {
+                    return Err(e);
+                }
+                Ok(vec![json!({ "count": before - records.len() })])
+            }
+        }
+    }
+
+    fn type_mut(&mut self, type_name: &str) -> Result<&mut StubType, StubError> {
+        self.types
+            .get_mut(type_name)
+            .ok_or_else(|| StubError::schema(format!("Type with name '{}' was not found", type_name)))
+    }
+
+    fn create_index(
+        &mut self,
+        index: IndexDefinition,
+        if_not_exists: bool,
+    ) -> Result<Vec<Value>, StubError> {
+        let name = index
+            .name
+            .unwrap_or_else(|| format!("{}[{}]", index.type_name, index.properties.join(",")));
+
+        let exists = self
+            .types
+            .values()
+            .any(|stub_type| stub_type.indexes.iter().any(|existing| existing.name == name));
+        if exists {
+            if if_not_exists {
+                return Ok(vec![]);
+            }
+            return Err(StubError::schema(format!("Index '{}' already exists", name)));
+        }
+
+        let stub_type = self.type_mut(&index.type_name)?;
+        for property in &index.properties {
+            if !stub_type.properties.contains(property) {
+                return Err(StubError::schema(format!(
+                    "Cannot create index on property '{}' because it does not exist in type '{}'",
+                    property, index.type_name
+                )));
+            }
+        }
+        stub_type.indexes.push(StubIndex {
+            name: name.clone(),
+            properties: index.properties,
+            unique: index.unique,
+        });
+
+        Ok(vec![json!({ "operation": "create index", "name": name })])
+    }
+
+    fn select(
+        &self,
+        projections: Vec<Projection>,
+        from: Option<String>,
+        filter: Option<Condition>,
+        order_by: Vec<(String, bool)>,
+        limit: Option<usize>,
+        params: &Map<String, Value>,
+    ) -> Result<Vec<Value>, StubError> {
+        let source: Vec<Map<String, Value>> = match from.as_deref() {
+            None => vec![Map::new()],
+            Some("schema:types") => self
+                .types
+                .keys()
+                .map(|name| {
+                    let mut record = Map::new();
+                    record.insert("name".to_string(), Value::String(name.clone()));
+                    record
+                })
+                .collect(),
+            Some(type_name) => {
+                if !self.types.contains_key(type_name) {
+                    return Err(StubError::schema(format!(
+                        "Type with name '{}' was not found",
+                        type_name
+                    )));
+                }
+                self.records.get(type_name).cloned().unwrap_or_default()
+            }
+        };
+
+        let mut rows = Vec::new();
+        for record in source {
+            if matches(filter.as_ref(), &record, params)? {
+                rows.push(record);
+            }
+        }
+
+        rows.sort_by(|a, b| {
+            for (field, descending) in &order_by {
+                let null = Value::Null;
+                let ordering = compare(a.get(field).unwrap_or(&null), b.get(field).unwrap_or(&null))
+                    .unwrap_or(Ordering::Equal);
+                if ordering != Ordering::Equal {
+                    return if *descending { ordering.reverse() } else { ordering };
+                }
+            }
+            Ordering::Equal
+        });
+
+        if let Some(limit) = limit {
+            rows.truncate(limit);
+        }
+
+        if projections.is_empty() {
+            return Ok(rows.into_iter().map(Value::Object).collect());
+        }
+
+        // Aggregate projections collapse the result into a single row
+        if projections.iter().any(|p| matches!(p.expr, Expr::CountAll)) {
+            let mut row = Map::new();
+            for projection in &projections {
+                let value = match projection.expr {
+                    Expr::CountAll => Value::from(rows.len()),
+                    _ => match rows.first() {
+                        Some(record) => evaluate(&projection.expr, record, params)?,
+                        None => Value::Null,
+                    },
+                };
+                row.insert(projection.alias.clone(), value);
+            }
+            return Ok(vec![Value::Object(row)]);
+        }
+
+        rows.iter()
+            .map(|record| {
+                let mut row = Map::new();
+                for projection in &projections {
+                    row.insert(
+                        projection.alias.clone(),
+                        evaluate(&projection.expr, record, params)?,
+                    );
+                }
+                Ok(Value::Object(row))
+            })
+            .collect()
+    }
+
+    fn insert(
+        &mut self,
+        type_name: String,
+        assignments: Vec<(String, Expr)>,
+        params: &Map<String, Value>,
+    ) -> Result<Vec<Value>, StubError> {
+        self.type_mut(&type_name)?;
+
+        let mut record = Map::new();
+        for (field, expr) in &assignments {
+            let value = evaluate(expr, &record, params)?;
+            record.insert(field.clone(), value);
+        }
+
+        self.next_position += 1;
+        let rid = format!("#1:{}", self.next_position);
+        record.insert("@rid".to_string(), Value::String(rid.clone()));
+        record.insert("@type".to_string(), Value::String(type_name.clone()));
+        record.insert("@cat".to_string(), Value::String("d".to_string()));
+
+        self.check_unique(&type_name, &record, &rid)?;
+
+        self.records
+            .entry(type_name)
+            .or_default()
+            .push(record.clone());
+
+        Ok(vec![Value::Object(record)])
+    }
+
+    fn update(
+        &mut self,
+        type_name: String,
+        assignments: Vec<(String, Expr)>,
+        removals: Vec<String>,
+        filter: Option<Condition>,
+        params: &Map<String, Value>,
+    ) -> Result<Vec<Value>, StubError> {
+        self.type_mut(&type_name)?;
+
+        let records = self.records.get(&type_name).cloned().unwrap_or_default();
+        let mut updated = records.clone();
+        let mut count = 0;
+
+        for record in updated.iter_mut() {
+            if !matches(filter.as_ref(), record, params)? {
+                continue;
+            }
+
+            let original = record.clone();
+            for (field, expr) in &assignments {
+                let value = evaluate(expr, &original, params)?;
+                record.insert(field.clone(), value);
+            }
+            for field in &removals {
+                record.remove(field);
+            }
+            count += 1;
+        }
+
+        // Validate against the other records before committing any change
+        self.records.insert(type_name.clone(), Vec::new());
+        for record in &updated {
+            let rid = record
+                .get("@rid")
+                .map(render)
+                .unwrap_or_default();
+            if let Err(e) = self.check_unique(&type_name, record, &rid) {
+                self.records.insert(type_name, records);
+                return Err(e);
+            }
+            self.records
+                .get_mut(&type_name)
+                .expect("records reset above")
+                .push(record.clone());
+        }
+
+        Ok(vec![json!({ "count": count })])
+    }
+
+    fn check_unique(
+        &self,
+        type_name: &str,
+        record: &Map<String, Value>,
+        rid: &str,
+    ) -> Result<(), StubError> {
+        let Some(stub_type) = self.types.get(type_name) else {
+            return Ok(());
+        };
+        let existing = self.records.get(type_name);
+
+        for index in stub_type.indexes.iter().filter(|index| index.unique) {
+            let keys: Vec<Value> = index
+                .properties
+                .iter()
+                .map(|property| record.get(property).cloned().unwrap_or(Value::Null))
+                .collect();
+
+            // Null keys are skipped, as with ArcadeDB's default null strategy
+            if keys.iter().any(Value::is_null) {
+                continue;
+            }
+
+            let clash = existing.into_iter().flatten().find(|other| {
+                other.get("@rid").map(render).as_deref() != Some(rid)
+                    && index
+                        .properties
+                        .iter()
+                        .zip(&keys)
+                        .all(|(property, key)| other.get(property) == Some(key))
+            });
+
+            if let Some(other) = clash {
+                let other_rid = other.get("@rid").map(render).unwrap_or_default();
+                return Err(StubError::duplicate_key(&index.name, &keys, &other_rid));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn now_millis() -> Value {
+    Value::from(chrono::Utc::now().timestamp_millis())
+}
+
+fn evaluate(expr: &Expr, record: &Map<String, Value>, params: &Map<String, Value>) -> Result<Value, StubError> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Param(name) => params
+            .get(name)
+            .cloned()
+            .ok_or_else(|| StubError::parse(format!("Parameter '{}' not defined", name))),
+        Expr::Field(field) => Ok(record.get(field).cloned().unwrap_or(Value::Null)),
+        Expr::SysDate => Ok(now_millis()),
+        Expr::CountAll => Err(StubError::parse("count(*) is only supported as a projection".to_string())),
+        Expr::Add(left, right) | Expr::Sub(left, right) => {
+            let left = evaluate(left, record, params)?;
+            let right = evaluate(right, record, params)?;
+            let sign = if matches!(expr, Expr::Add(..)) { 1 } else { -1 };
+            match (left.as_i64(), right.as_i64(), &left, &right) {
+                (Some(l), Some(r), _, _) => Ok(Value::from(l + sign * r)),
+                (_, _, Value::String(l), Value::String(r)) if sign == 1 => {
+                    Ok(Value::String(format!("{}{}", l, r)))
+                }
+                _ => match (left.as_f64(), right.as_f64()) {
+                    (Some(l), Some(r)) => Ok(Value::from(l + sign as f64 * r)),
+                    _ => Ok(Value::Null),
+                },
+            }
+        }
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.as_f64()?.partial_cmp(&r.as_f64()?),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::Null, _) => Some(Ordering::Less),
+        (_, Value::Null) => Some(Ordering::Greater),
+        _ => None,
+    }
+}
+
+fn matches(
+    condition: Option<&Condition>,
+    record: &Map<String, Value>,
+    params: &Map<String, Value>,
+) -> Result<bool, StubError> {
+    let Some(condition) = condition else {
+        return Ok(true);
+    };
+
+    Ok(match condition {
+        Condition::Compare(left, op, right) => {
+            let left = evaluate(left, record, params)?;
+            let right = evaluate(right, record, params)?;
+            // Comparisons involving null are never true in SQL
+            if left.is_null() || right.is_null() {
+                false
+            } else {
+                match compare(&left, &right) {
+                    None => *op == CompareOp::Ne,
+                    Some(ordering) => match op {
+                        CompareOp::Eq => ordering == Ordering::Equal,
+                        CompareOp::Ne => ordering != Ordering::Equal,
+                        CompareOp::Lt => ordering == Ordering::Less,
+                        CompareOp::Le => ordering != Ordering::Greater,
+                        CompareOp::Gt => ordering == Ordering::Greater,
+                        CompareOp::Ge => ordering != Ordering::Less,
+                    },
+                }
+            }
+        }
+        Condition::IsNull(expr, expect_null) => {
+            evaluate(expr, record, params)?.is_null() == *expect_null
+        }
+        Condition::In(expr, candidates) => {
+            let value = evaluate(expr, record, params)?;
+            let mut found = false;
+            for candidate in candidates {
+                match evaluate(candidate, record, params)? {
+                    Value::Array(items) => found |= items.contains(&value),
+                    other => found |= other == value,
+                }
+            }
+            found
+        }
+        Condition::And(left, right) => {
+            matches(Some(left), record, params)? && matches(Some(right), record, params)?
+        }
+        Condition::Or(left, right) => {
+            matches(Some(left), record, params)? || matches(Some(right), record, params)?
+        }
+        Condition::Not(inner) => !matches(Some(inner), record, params)?,
+    })
+}
@@ -0,0 +1,8 @@
+// Test support
+// Stand-ins for external services so integration tests run without Docker.
+// Only compiled with the `test-support` feature.
+
+pub mod arcade_sql;
+pub mod arcade_stub;
+
+pub use arcade_stub::ArcadeStub;
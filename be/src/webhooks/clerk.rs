@@ -0,0 +1,151 @@
+//! Clerk user events, keeping Profile in step with Clerk
+//!
+//! Handlers are idempotent: Svix retries deliveries and may reorder them, so
+//! every event re-reads the profile through its Clerk identity before acting.
+
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::app_state::AppState;
+use crate::auth::clerk::PROVIDER_NAME;
+use crate::db::error::DbError;
+use crate::erasure::Requester;
+use crate::patch::Patch;
+use crate::repository::{find_by_identity, find_or_create_by_identity, subject_hash, ProfileUpdate};
+use crate::validation::DISPLAY_NAME;
+
+/// Envelope shared by every Clerk webhook event
+#[derive(Debug, Deserialize)]
+pub struct ClerkEvent {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub data: serde_json::Value,
+}
+
+/// The parts of Clerk's user object we mirror
+#[derive(Debug, Deserialize)]
+pub struct ClerkUser {
+    pub id: String,
+    #[serde(default)]
+    pub first_name: Option<String>,
+    #[serde(default)]
+    pub last_name: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+impl ClerkUser {
+    /// "First Last", falling back to the username, normalized and cut to
+    /// fit [`DISPLAY_NAME`]
+    pub fn display_name(&self) -> Option<String> {
+        let full_name = [&self.first_name, &self.last_name]
+            .into_iter()
+            .flatten()
+            .map(|part| part.trim())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        DISPLAY_NAME
+            .fit(&full_name)
+            .or_else(|| self.username.as_deref().and_then(|username| DISPLAY_NAME.fit(username)))
+    }
+}
+
+/// Payload of `user.deleted`
+#[derive(Debug, Deserialize)]
+pub struct DeletedObject {
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("malformed event payload: {0}")]
+    Payload(#[from] serde_json::Error),
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
+
+/// Apply one event; unknown event types are ignored
+pub async fn handle_event(app_state: &AppState, event: ClerkEvent) -> Result<(), WebhookError> {
+    match event.event_type.as_str() {
+        "user.created" | "user.updated" => {
+            let user: ClerkUser = serde_json::from_value(event.data)?;
+            sync_user(app_state, &user).await?;
+        }
+        "user.deleted" => {
+            let deleted: DeletedObject = serde_json::from_value(event.data)?;
+            if let Some(clerk_id) = deleted.id {
+                delete_user(app_state, &clerk_id).await?;
+            }
+        }
+        other => tracing::debug!("Ignoring Clerk webhook event '{}'", other),
+    }
+
+    Ok(())
+}
+
+/// Create the profile for a Clerk user, or update its display name
+async fn sync_user(app_state: &AppState, user: &ClerkUser) -> Result<(), DbError> {
+    let profiles = app_state.profiles.as_ref();
+
+    // A late or replayed event must not bring an erased account back
+    if find_by_identity(profiles, app_state.identities.as_ref(), PROVIDER_NAME, &user.id)
+        .await?
+        .is_none()
+        && app_state.erasures.is_erased(&subject_hash(PROVIDER_NAME, &user.id)).await?
+    {
+        tracing::debug!("Ignoring Clerk update for erased clerkId {}", user.id);
+        return Ok(());
+    }
+
+    let existing = find_or_create_by_identity(profiles, app_state.identities.as_ref(), PROVIDER_NAME, &user.id, || {
+        user.display_name().unwrap_or_else(|| "User".to_string())
+    })
+    .await?;
+
+    if existing.is_deleted() {
+        tracing::warn!("Ignoring Clerk update for deleted profile: userId={}", existing.user_id);
+        return Ok(());
+    }
+
+    if let Some(name) = user.display_name()
+        && existing.display_name.as_deref() != Some(name.as_str())
+    {
+        profiles
+            .update(
+                &existing.user_id,
+                ProfileUpdate {
+                    display_name: Patch::Value(name),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?;
+        tracing::info!("Synced profile from webhook: userId={}", existing.user_id);
+    }
+
+    Ok(())
+}
+
+/// Tombstone the profile of a deleted Clerk user and schedule its erasure
+/// right away; the user can no longer sign in to cancel it
+async fn delete_user(app_state: &AppState, clerk_id: &str) -> Result<(), DbError> {
+    let profiles = app_state.profiles.as_ref();
+
+    match find_by_identity(profiles, app_state.identities.as_ref(), PROVIDER_NAME, clerk_id).await? {
+        Some(profile) if !profile.is_deleted() => {
+            let profile = profiles.tombstone(&profile.user_id).await?;
+            tracing::info!("Tombstoned profile from webhook: userId={}", profile.user_id);
+            // Replaces any later schedule the user set up themselves
+            profiles
+                .schedule_deletion(&profile.user_id, &Requester::Clerk.to_string(), Utc::now())
+                .await?;
+        }
+        Some(_) => tracing::debug!("Profile for clerkId {} already deleted", clerk_id),
+        None => tracing::debug!("No profile for deleted clerkId {}", clerk_id),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,4 @@
+//! Inbound webhooks from external services
+
+pub mod clerk;
+pub mod svix;
@@ -0,0 +1,216 @@
+//! Migrations, error mapping and the ArcadeDB repositories against
+//! `testing::ArcadeStub`
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use cynnycty_backend::db::error::DbError;
+use cynnycty_backend::db::migrations::{applied_migrations, load_migrations, run_migrations, MigrationError};
+use cynnycty_backend::patch::Patch;
+use cynnycty_backend::repository::{
+    ArcadeAuthIdentityRepository, ArcadeProfileRepository, AuthIdentityRepository, NewAuthIdentity, NewProfile,
+    ProfileRepository, ProfileUpdate,
+};
+use cynnycty_backend::testing::ArcadeStub;
+
+use common::{migrated, TestApp};
+
+fn new_profile(user_id: &str, clerk_id: Option<&str>) -> NewProfile {
+    NewProfile {
+        user_id: user_id.to_string(),
+        clerk_id: clerk_id.map(str::to_string),
+        display_name: Some("Ada".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn migrations_apply_once() {
+    let stub = ArcadeStub::start().await;
+    let db = stub.connection("cynnycty");
+    let dir = common::config().database.migrations_dir.clone();
+    let files = load_migrations(&dir).unwrap();
+
+    assert_eq!(run_migrations(&db, &dir).await.unwrap(), files.len());
+    assert_eq!(run_migrations(&db, &dir).await.unwrap(), 0);
+
+    let applied = applied_migrations(&db).await.unwrap();
+    assert_eq!(applied.len(), files.len());
+    for (record, file) in applied.iter().zip(&files) {
+        assert_eq!(record.version, file.version);
+        assert_eq!(record.checksum, file.checksum);
+    }
+}
+
+#[tokio::test]
+async fn rejected_statements_are_not_retried() {
+    let dir = std::env::temp_dir().join(format!("cynnycty-migrations-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("0001_broken.sql"), "CREATE DOCUMENT TYPE Broken IF NOT EXISTS;\nCREATE NONSENSE;\n").unwrap();
+
+    let stub = ArcadeStub::start().await;
+    let result = run_migrations(&stub.connection("cynnycty"), &dir).await;
+    std::fs::remove_dir_all(&dir).ok();
+
+    let error = result.unwrap_err();
+    assert!(matches!(error, MigrationError::Statement { statement: 2, .. }), "{:?}", error);
+    assert!(!error.is_transient());
+}
+
+#[tokio::test]
+async fn unreachable_database_is_retried() {
+    let stub = ArcadeStub::start().await;
+    let db = stub.connection("cynnycty");
+    drop(stub);
+
+    let error = run_migrations(&db, &common::config().database.migrations_dir)
+        .await
+        .unwrap_err();
+    assert!(error.is_transient(), "{:?}", error);
+}
+
+#[tokio::test]
+async fn duplicate_keys_are_conflicts() {
+    let stub = ArcadeStub::start().await;
+    let profiles = ArcadeProfileRepository::new(migrated(&stub).await);
+
+    profiles.create(new_profile("user-1", Some("clerk-1"))).await.unwrap();
+
+    let same_user = profiles.create(new_profile("user-1", None)).await;
+    assert!(matches!(same_user, Err(DbError::Conflict(_))), "{:?}", same_user);
+
+    let same_clerk_id = profiles.create(new_profile("user-2", Some("clerk-1"))).await;
+    assert!(matches!(same_clerk_id, Err(DbError::Conflict(_))), "{:?}", same_clerk_id);
+
+    assert_eq!(stub.records("cynnycty", "Profile").len(), 1);
+}
+
+#[tokio::test]
+async fn profile_repository_round_trip() {
+    let stub = ArcadeStub::start().await;
+    let profiles = ArcadeProfileRepository::new(migrated(&stub).await);
+
+    let created = profiles.create(new_profile("user-1", Some("clerk-1"))).await.unwrap();
+    assert_eq!(created.user_id, "user-1");
+    assert_eq!(created.version, 1);
+    assert!(created.created_at.is_some());
+
+    let updated = profiles
+        .update(
+            "user-1",
+            ProfileUpdate {
+                about_me: Patch::Value("Analyst".to_string()),
+                ..Default::default()
+            },
+            Some(1),
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.about_me.as_deref(), Some("Analyst"));
+    assert_eq!(updated.display_name.as_deref(), Some("Ada"));
+    assert_eq!(updated.version, 2);
+
+    let stale = profiles.update("user-1", ProfileUpdate::default(), Some(1)).await;
+    assert!(matches!(stale, Err(DbError::Conflict(_))), "{:?}", stale);
+
+    let found = profiles.find_by_clerk_id("clerk-1").await.unwrap().unwrap();
+    assert_eq!(found.user_id, "user-1");
+
+    profiles.set_handle("user-1", "Ada_L").await.unwrap();
+    assert_eq!(
+        profiles.find_by_handle("ada_l").await.unwrap().map(|p| p.user_id),
+        Some("user-1".to_string())
+    );
+    profiles.create(new_profile("user-2", None)).await.unwrap();
+    let taken = profiles.set_handle("user-2", "ADA_L").await;
+    assert!(matches!(taken, Err(DbError::Conflict(_))), "{:?}", taken);
+
+    let tombstoned = profiles.tombstone("user-1").await.unwrap();
+    assert!(tombstoned.is_deleted());
+    assert_eq!(tombstoned.display_name, None);
+
+    let missing = profiles.update("nobody", ProfileUpdate::default(), None).await;
+    assert!(matches!(missing, Err(DbError::NotFound(_))), "{:?}", missing);
+}
+
+#[tokio::test]
+async fn identity_repository_round_trip() {
+    let stub = ArcadeStub::start().await;
+    let identities = ArcadeAuthIdentityRepository::new(migrated(&stub).await);
+
+    let identity = NewAuthIdentity {
+        user_id: "user-1".to_string(),
+        provider: "dev".to_string(),
+        subject: "ada".to_string(),
+    };
+    identities.create(identity.clone()).await.unwrap();
+
+    let duplicate = identities.create(identity).await;
+    assert!(matches!(duplicate, Err(DbError::Conflict(_))), "{:?}", duplicate);
+
+    let found = identities.find("dev", "ada").await.unwrap().unwrap();
+    assert_eq!(found.user_id, "user-1");
+    assert_eq!(identities.list_for_user("user-1").await.unwrap().len(), 1);
+
+    identities.delete("user-1", "dev", "ada").await.unwrap();
+    assert!(identities.find("dev", "ada").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn router_on_the_stub() {
+    let stub = ArcadeStub::start().await;
+    let app = TestApp::arcade(&stub).await;
+    let token = app.token("ada");
+
+    let first = app.get("/api/v1/profiles/me", Some(&token)).await;
+    assert_eq!(first.status, StatusCode::OK);
+
+    let updated = app
+        .request(
+            Method::PUT,
+            "/api/v1/profiles/me",
+            Some(&token),
+            Some(json!({"display_name": "Ada Lovelace"})),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK);
+    assert_eq!(updated.body["user_id"], first.body["user_id"]);
+    assert_eq!(updated.body["display_name"], "Ada Lovelace");
+
+    assert_eq!(stub.records("cynnycty", "Profile").len(), 1);
+    assert_eq!(stub.records("cynnycty", "AuthIdentity").len(), 1);
+}
+
+#[tokio::test]
+async fn readiness_probe_tracks_the_database() {
+    let stub = ArcadeStub::start().await;
+    let app = TestApp::arcade(&stub).await;
+    let token = app.token("ada");
+    let database = &app.state.dependencies.database;
+
+    app.state.db.probe(database).await;
+    assert!(database.is_up());
+    assert_eq!(app.get("/api/v1/profiles/me", Some(&token)).await.status, StatusCode::OK);
+
+    // Dropping the stub stops new connections; pooled ones may still be
+    // served, so probe through a fresh client
+    let unreachable = stub.connection("cynnycty");
+    drop(stub);
+    unreachable.probe(database).await;
+    assert!(!database.is_up());
+    assert_eq!(
+        app.get("/api/v1/profiles/me", Some(&token)).await.status,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+
+    let health = app.get("/health", None).await;
+    assert_eq!(health.body["status"], "degraded");
+    assert_eq!(health.body["dependencies"]["database"]["status"], "down");
+    assert!(health.body["dependencies"]["database"].get("last_error").is_none());
+
+    // Recovers once a database answers again
+    let stub = ArcadeStub::start().await;
+    stub.connection("cynnycty").probe(database).await;
+    assert!(database.is_up());
+}
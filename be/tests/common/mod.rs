@@ -0,0 +1,170 @@
+//! Shared setup for the integration tests: `routes::router` with dev auth,
+//! driven in-process through `tower::ServiceExt::oneshot`, on in-memory
+//! repositories or on an [`ArcadeStub`]
+
+// Each test binary uses a different part of this module
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::{self, Body},
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use cynnycty_backend::app_state::AppState;
+use cynnycty_backend::auth::dev::DevAuth;
+use cynnycty_backend::auth::provider;
+use cynnycty_backend::config::AppConfig;
+use cynnycty_backend::db::connection::DatabaseConnection;
+use cynnycty_backend::db::migrations::run_migrations;
+use cynnycty_backend::dependencies::Dependencies;
+use cynnycty_backend::repository::Repositories;
+use cynnycty_backend::routes;
+use cynnycty_backend::testing::ArcadeStub;
+
+pub const DEV_SECRET: &str = "integration-tests-dev-secret-0123456789";
+pub const CLERK_WEBHOOK_SECRET: &str = "whsec_MfKQ9r8GKYqrTwjUPD8ILPZIo2LaLaSw";
+
+/// Dev auth, Clerk webhooks and the crate's own migrations; everything else
+/// at defaults
+pub fn config() -> Arc<AppConfig> {
+    config_with(&[])
+}
+
+/// [`config`] with some variables set, e.g. shorter handle periods
+pub fn config_with(overrides: &[(&str, &str)]) -> Arc<AppConfig> {
+    let config = AppConfig::from_sources(
+        |key| {
+            if let Some((_, value)) = overrides.iter().find(|(name, _)| *name == key) {
+                return Some(value.to_string());
+            }
+            match key {
+                "AUTH_PROVIDER" => Some("dev".to_string()),
+                "DEV_AUTH_SECRET" => Some(DEV_SECRET.to_string()),
+                "CLERK_WEBHOOK_SECRET" => Some(CLERK_WEBHOOK_SECRET.to_string()),
+                "MIGRATIONS_DIR" => Some(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations").to_string()),
+                _ => None,
+            }
+        },
+        None,
+    )
+    .expect("test config is valid");
+    Arc::new(config)
+}
+
+/// A connection to a database on `stub` with every migration applied
+pub async fn migrated(stub: &ArcadeStub) -> DatabaseConnection {
+    let db = stub.connection("cynnycty");
+    run_migrations(&db, &config().database.migrations_dir)
+        .await
+        .expect("migrations apply to the stub");
+    db
+}
+
+/// A connection that is never contacted, for apps on in-memory repositories
+pub fn unused_db() -> DatabaseConnection {
+    DatabaseConnection::new("127.0.0.1", 9, "root", "", "cynnycty").expect("valid connection settings")
+}
+
+/// A response with its body parsed as JSON (`Null` when empty)
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+/// The router and the state behind it
+pub struct TestApp {
+    pub state: AppState,
+    router: Router,
+}
+
+impl TestApp {
+    /// Router on in-memory repositories, with every dependency up
+    pub fn in_memory() -> Self {
+        Self::new(unused_db(), Repositories::in_memory())
+    }
+
+    /// Router on the ArcadeDB repositories, against a migrated database on
+    /// `stub`
+    pub async fn arcade(stub: &ArcadeStub) -> Self {
+        let db = migrated(stub).await;
+        let repositories = Repositories::arcade(&db);
+        Self::new(db, repositories)
+    }
+
+    /// Router on in-memory repositories with [`config_with`] `overrides`
+    pub fn in_memory_with(overrides: &[(&str, &str)]) -> Self {
+        Self::with_config(config_with(overrides), unused_db(), Repositories::in_memory())
+    }
+
+    pub fn new(db: DatabaseConnection, repositories: Repositories) -> Self {
+        Self::with_config(config(), db, repositories)
+    }
+
+    pub fn with_config(config: Arc<AppConfig>, db: DatabaseConnection, repositories: Repositories) -> Self {
+        let dependencies = Arc::new(Dependencies::default());
+        dependencies.database.mark_up();
+
+        let auth = provider::from_config(&config.auth, &config.clerk).expect("dev auth provider");
+        auth.start(dependencies.clone());
+
+        let state = AppState::new(config, db, auth, repositories, dependencies);
+        Self {
+            router: routes::router(state.clone()),
+            state,
+        }
+    }
+
+    /// A dev session token for `subject`, named after it
+    pub fn token(&self, subject: &str) -> String {
+        DevAuth::new(DEV_SECRET)
+            .mint(
+                subject,
+                Some(format!("{}@example.com", subject)),
+                Some(subject.to_string()),
+                Duration::from_secs(600),
+            )
+            .expect("mint dev token")
+            .0
+    }
+
+    pub async fn request(&self, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .expect("valid request");
+
+        self.send(request).await
+    }
+
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router.clone().oneshot(request).await.expect("router is infallible");
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = body::to_bytes(response.into_body(), usize::MAX).await.expect("read body");
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes).expect("JSON body")
+        };
+
+        TestResponse { status, headers, body }
+    }
+
+    pub async fn get(&self, uri: &str, token: Option<&str>) -> TestResponse {
+        self.request(Method::GET, uri, token, None).await
+    }
+}
@@ -0,0 +1,135 @@
+//! The export archive matches the documented format in
+//! `fixtures/export-v1.json`, and exports are started and listed on one
+//! collection
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+
+use cynnycty_backend::auth::Role;
+use cynnycty_backend::export::{build_archive, FORMAT, FORMAT_VERSION};
+use cynnycty_backend::repository::NewHandleHistory;
+
+use common::TestApp;
+
+const FIXTURE: &str = include_str!("../fixtures/export-v1.json");
+
+/// Fail unless `actual` has exactly the keys of `expected` at every level,
+/// with the same JSON types; null on either side matches anything, and array
+/// items are compared against the fixture's first item
+fn assert_same_shape(path: &str, expected: &Value, actual: &Value) {
+    match (expected, actual) {
+        (Value::Null, _) | (_, Value::Null) => {}
+        (Value::Object(expected), Value::Object(actual)) => {
+            let expected_keys: Vec<_> = expected.keys().collect();
+            let actual_keys: Vec<_> = actual.keys().collect();
+            assert_eq!(actual_keys, expected_keys, "keys of {}", path);
+            for (key, value) in expected {
+                assert_same_shape(&format!("{}.{}", path, key), value, &actual[key]);
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if let Some(item) = expected.first() {
+                assert!(!actual.is_empty(), "{} is empty; the test data should fill it", path);
+                for (idx, value) in actual.iter().enumerate() {
+                    assert_same_shape(&format!("{}[{}]", path, idx), item, value);
+                }
+            }
+        }
+        (Value::String(_), Value::String(_)) | (Value::Number(_), Value::Number(_)) | (Value::Bool(_), Value::Bool(_)) => {}
+        (expected, actual) => panic!("{}: expected {} but got {}", path, expected, actual),
+    }
+}
+
+#[tokio::test]
+async fn archive_matches_the_fixture() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+
+    let me = app.get("/api/v1/profiles/me", Some(&token)).await;
+    let user_id = me.body["user_id"].as_str().unwrap().to_string();
+
+    let steps = [
+        (Method::PUT, "/api/v1/profiles/me", json!({
+            "display_name": "Ada Example",
+            "about_me": "Collects maps.",
+            "avatar_url": "https://img.clerk.com/avatar.png",
+        })),
+        (Method::PUT, "/api/v1/profiles/me/handle", json!({"handle": "ada_maps"})),
+        (Method::POST, "/api/v1/tokens", json!({"name": "backup script", "scopes": ["profile:read"]})),
+    ];
+    for (method, uri, body) in steps {
+        let response = app.request(method, uri, Some(&token), Some(body)).await;
+        assert!(response.status.is_success(), "{} {}", uri, response.status);
+    }
+    app.state.profiles.set_roles(&user_id, &[Role::Moderator]).await.unwrap();
+    app.state
+        .handle_history
+        .record(NewHandleHistory {
+            handle: "ada_example".to_string(),
+            user_id: user_id.clone(),
+            expires_at: Utc::now() + Duration::days(90),
+        })
+        .await
+        .unwrap();
+
+    let archive = serde_json::to_value(build_archive(&app.state, &user_id).await.unwrap()).unwrap();
+    let fixture: Value = serde_json::from_str(FIXTURE).unwrap();
+
+    assert_eq!(fixture["format"], FORMAT);
+    assert_eq!(fixture["version"], FORMAT_VERSION);
+    assert_eq!(archive["format"], fixture["format"]);
+    assert_eq!(archive["version"], fixture["version"]);
+    assert_same_shape("archive", &fixture, &archive);
+
+    assert_eq!(archive["user_id"], user_id.as_str());
+    assert_eq!(archive["profile"]["handle"], "ada_maps");
+    assert_eq!(archive["profile"]["roles"], json!(["moderator"]));
+    assert_eq!(archive["identities"][0]["subject"], "ada");
+    assert_eq!(archive["released_handles"][0]["handle"], "ada_example");
+}
+
+#[tokio::test]
+async fn archive_leaves_out_secrets() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+    let me = app.get("/api/v1/profiles/me", Some(&token)).await;
+    let created = app
+        .request(
+            Method::POST,
+            "/api/v1/tokens",
+            Some(&token),
+            Some(json!({"name": "ci", "scopes": ["profile:read"]})),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+
+    let archive = build_archive(&app.state, me.body["user_id"].as_str().unwrap()).await.unwrap();
+    let text = serde_json::to_string(&archive).unwrap();
+
+    let secret = created.body["token"].as_str().unwrap();
+    assert!(!text.contains(secret));
+    assert!(!text.contains("token_hash"));
+}
+
+#[tokio::test]
+async fn exports_are_started_on_the_collection() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+
+    let started = app.request(Method::POST, "/api/v1/profiles/me/exports", Some(&token), None).await;
+    assert_eq!(started.status, StatusCode::ACCEPTED);
+    let export_id = started.body["export_id"].as_str().unwrap();
+
+    let listed = app.get("/api/v1/profiles/me/exports", Some(&token)).await;
+    assert_eq!(listed.status, StatusCode::OK);
+    assert_eq!(listed.body[0]["export_id"], export_id);
+
+    let polled = app.get(&format!("/api/v1/profiles/me/exports/{}", export_id), Some(&token)).await;
+    assert_eq!(polled.status, StatusCode::OK);
+
+    let old = app.request(Method::POST, "/api/v1/profiles/me/export", Some(&token), None).await;
+    assert_eq!(old.status, StatusCode::NOT_FOUND);
+}
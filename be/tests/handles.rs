@@ -0,0 +1,129 @@
+//! Handle availability, changes, the change cooldown and redirects from
+//! released handles
+
+mod common;
+
+use axum::http::{header, Method, StatusCode};
+use serde_json::json;
+
+use common::{TestApp, TestResponse};
+
+/// Set `token`'s handle
+async fn change(app: &TestApp, token: &str, handle: &str) -> TestResponse {
+    app.request(
+        Method::PUT,
+        "/api/v1/profiles/me/handle",
+        Some(token),
+        Some(json!({ "handle": handle })),
+    )
+    .await
+}
+
+async fn available(app: &TestApp, handle: &str, token: Option<&str>) -> (bool, serde_json::Value) {
+    let response = app.get(&format!("/api/v1/handles/{}/availability", handle), token).await;
+    assert_eq!(response.status, StatusCode::OK);
+    (response.body["available"].as_bool().unwrap(), response.body["reason"].clone())
+}
+
+/// No cooldown between changes; released handles redirect for a day
+fn unlimited() -> TestApp {
+    TestApp::in_memory_with(&[("HANDLE_CHANGE_COOLDOWN_DAYS", "0"), ("HANDLE_REDIRECT_DAYS", "1")])
+}
+
+#[tokio::test]
+async fn availability_reports_the_reason() {
+    let app = TestApp::in_memory();
+    let ada = app.token("ada");
+    assert_eq!(change(&app, &ada, "Ada").await.status, StatusCode::OK);
+
+    assert_eq!(available(&app, "grace", None).await, (true, json!(null)));
+    assert_eq!(available(&app, "admin", None).await, (false, json!("reserved")));
+    assert_eq!(available(&app, "1ada", None).await, (false, json!("must_start_with_letter")));
+    assert_eq!(available(&app, "ADA", None).await, (false, json!("taken")));
+
+    // Your own handle is available to you
+    assert_eq!(available(&app, "ada", Some(&ada)).await, (true, json!(null)));
+    assert_eq!(available(&app, "ada", Some(&app.token("bob"))).await, (false, json!("taken")));
+}
+
+#[tokio::test]
+async fn handles_are_unique_regardless_of_case() {
+    let app = TestApp::in_memory();
+
+    let ada = change(&app, &app.token("ada"), "Ada").await;
+    assert_eq!(ada.status, StatusCode::OK);
+    assert_eq!(ada.body["handle"], "Ada");
+
+    let bob = change(&app, &app.token("bob"), "aDA").await;
+    assert_eq!(bob.status, StatusCode::CONFLICT);
+    assert_eq!(bob.body["error"], "handle_taken");
+
+    let invalid = change(&app, &app.token("bob"), "a-b").await;
+    assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(invalid.body["fields"][0]["field"], "handle");
+    assert_eq!(invalid.body["fields"][0]["code"], "invalid_characters");
+
+    let by_handle = app.get("/api/v1/profiles/by-handle/ADA", None).await;
+    assert_eq!(by_handle.status, StatusCode::OK);
+    assert_eq!(by_handle.body["handle"], "Ada");
+}
+
+#[tokio::test]
+async fn changes_are_limited_by_the_cooldown() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+
+    // The first handle and case-only changes are free
+    assert_eq!(change(&app, &token, "ada").await.status, StatusCode::OK);
+    let recased = change(&app, &token, "Ada").await;
+    assert_eq!(recased.status, StatusCode::OK);
+    assert_eq!(recased.body["handle"], "Ada");
+
+    let limited = change(&app, &token, "ada_l").await;
+    assert_eq!(limited.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(limited.body["error"], "handle_change_cooldown");
+    let retry_after: i64 = limited.headers[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+    let thirty_days = 30 * 24 * 60 * 60;
+    assert!((thirty_days - 60..=thirty_days).contains(&retry_after), "{}", retry_after);
+
+    // A case-only change released nothing
+    assert_eq!(available(&app, "ada", Some(&app.token("bob"))).await, (false, json!("taken")));
+    assert_eq!(
+        app.get("/api/v1/profiles/by-handle/ada", None).await.body["handle"],
+        "Ada"
+    );
+}
+
+#[tokio::test]
+async fn released_handles_redirect_and_stay_with_their_owner() {
+    let app = unlimited();
+    let ada = app.token("ada");
+    let bob = app.token("bob");
+    assert_eq!(change(&app, &ada, "ada").await.status, StatusCode::OK);
+    assert_eq!(change(&app, &ada, "ada_l").await.status, StatusCode::OK);
+
+    let redirect = app.get("/api/v1/profiles/by-handle/ADA", None).await;
+    assert_eq!(redirect.status, StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(redirect.headers[header::LOCATION], "/api/v1/profiles/by-handle/ada_l");
+
+    // Nobody else may claim it meanwhile, but its former owner may
+    assert_eq!(available(&app, "ada", Some(&bob)).await, (false, json!("taken")));
+    assert_eq!(change(&app, &bob, "ada").await.status, StatusCode::CONFLICT);
+    assert_eq!(available(&app, "ada", Some(&ada)).await, (true, json!(null)));
+    let reclaimed = change(&app, &ada, "ada").await;
+    assert_eq!(reclaimed.status, StatusCode::OK);
+    assert_eq!(reclaimed.body["handle"], "ada");
+    assert_eq!(app.get("/api/v1/profiles/by-handle/ada", None).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn redirects_expire() {
+    let app = TestApp::in_memory_with(&[("HANDLE_CHANGE_COOLDOWN_DAYS", "0"), ("HANDLE_REDIRECT_DAYS", "0")]);
+    let ada = app.token("ada");
+    assert_eq!(change(&app, &ada, "ada").await.status, StatusCode::OK);
+    assert_eq!(change(&app, &ada, "ada_l").await.status, StatusCode::OK);
+
+    assert_eq!(app.get("/api/v1/profiles/by-handle/ada", None).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(available(&app, "ada", Some(&app.token("bob"))).await, (true, json!(null)));
+    assert_eq!(change(&app, &app.token("bob"), "ada").await.status, StatusCode::OK);
+}
@@ -0,0 +1,360 @@
+//! `/api/v1/profiles/me` end to end on in-memory repositories
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use cynnycty_backend::auth::Role;
+use cynnycty_backend::db::error::DbError;
+use cynnycty_backend::patch::Patch;
+use cynnycty_backend::repository::memory::InMemoryProfileRepository;
+use cynnycty_backend::repository::{NewProfile, Profile, ProfileRepository, ProfileUpdate, Repositories};
+
+use common::{TestApp, TestResponse};
+
+/// A write to `/api/v1/profiles/me` with an optional `If-Match`
+async fn write(app: &TestApp, method: Method, token: &str, if_match: Option<&str>, body: Value) -> TestResponse {
+    let mut request = Request::builder()
+        .method(method)
+        .uri("/api/v1/profiles/me")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(tag) = if_match {
+        request = request.header(header::IF_MATCH, tag);
+    }
+    app.send(request.body(Body::from(body.to_string())).unwrap()).await
+}
+
+fn etag(response: &TestResponse) -> String {
+    response.headers[header::ETAG].to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn first_request_provisions_one_profile() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+
+    let first = app.get("/api/v1/profiles/me", Some(&token)).await;
+    assert_eq!(first.status, StatusCode::OK);
+    let user_id = first.body["user_id"].as_str().expect("user_id").to_string();
+    assert_eq!(first.body["display_name"], "ada");
+    assert_eq!(first.body["email"], "ada@example.com");
+
+    let second = app.get("/api/v1/profiles/me", Some(&token)).await;
+    assert_eq!(second.status, StatusCode::OK);
+    assert_eq!(second.body["user_id"], user_id.as_str());
+
+    let identities = app.state.identities.list_for_user(&user_id).await.unwrap();
+    assert_eq!(identities.len(), 1);
+    assert_eq!(identities[0].provider, "dev");
+    assert_eq!(identities[0].subject, "ada");
+}
+
+#[tokio::test]
+async fn users_get_separate_profiles() {
+    let app = TestApp::in_memory();
+
+    let ada = app.get("/api/v1/profiles/me", Some(&app.token("ada"))).await;
+    let bob = app.get("/api/v1/profiles/me", Some(&app.token("bob"))).await;
+
+    assert_ne!(ada.body["user_id"], bob.body["user_id"]);
+}
+
+#[tokio::test]
+async fn put_updates_the_stored_profile() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+
+    let updated = app
+        .request(
+            Method::PUT,
+            "/api/v1/profiles/me",
+            Some(&token),
+            Some(json!({"display_name": "Ada Lovelace", "about_me": "Analyst"})),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK);
+    assert_eq!(updated.body["display_name"], "Ada Lovelace");
+    assert_eq!(updated.body["about_me"], "Analyst");
+
+    let read = app.get("/api/v1/profiles/me", Some(&token)).await;
+    assert_eq!(read.body["display_name"], "Ada Lovelace");
+    assert_eq!(read.body["about_me"], "Analyst");
+    assert_eq!(read.body["user_id"], updated.body["user_id"]);
+}
+
+#[tokio::test]
+async fn requires_a_token() {
+    let app = TestApp::in_memory();
+
+    assert_eq!(app.get("/api/v1/profiles/me", None).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        app.get("/api/v1/profiles/me", Some("not-a-token")).await.status,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn public_profiles_need_only_the_database() {
+    let app = TestApp::in_memory();
+    let me = app.get("/api/v1/profiles/me", Some(&app.token("ada"))).await;
+    let public_uri = format!("/api/v1/profiles/{}", me.body["user_id"].as_str().unwrap());
+
+    app.state.dependencies.auth.mark_down();
+
+    let public = app.get(&public_uri, None).await;
+    assert_eq!(public.status, StatusCode::OK);
+    assert_eq!(public.body["display_name"], "ada");
+    assert_eq!(
+        app.get("/api/v1/profiles/by-handle/nobody", None).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        app.get("/api/v1/profiles/me", Some(&app.token("ada"))).await.status,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+
+    app.state.dependencies.database.mark_down();
+
+    assert_eq!(app.get(&public_uri, None).await.status, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn provisioning_fits_token_names_to_the_rules() {
+    let app = TestApp::in_memory();
+    let (token, _) = cynnycty_backend::auth::dev::DevAuth::new(common::DEV_SECRET)
+        .mint(
+            "ada",
+            None,
+            Some(format!("\u{202e} Ada {}", "y".repeat(80))),
+            std::time::Duration::from_secs(600),
+        )
+        .unwrap();
+
+    let me = app.get("/api/v1/profiles/me", Some(&token)).await;
+
+    let name = me.body["display_name"].as_str().unwrap();
+    assert_eq!(name.chars().count(), 50);
+    assert!(name.starts_with("Ada yyy"), "{:?}", name);
+
+    // The session shows the same fitted name, not the raw claim
+    let session = app.get("/api/v1/session", Some(&token)).await;
+    assert_eq!(session.body["display_name"], name);
+}
+
+#[tokio::test]
+async fn patch_leaves_absent_fields_and_clears_nulls() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+    let set = json!({"display_name": "Ada", "about_me": "Hi", "avatar_url": "https://img.clerk.com/ada.png"});
+    assert_eq!(write(&app, Method::PUT, &token, None, set).await.status, StatusCode::OK);
+
+    let cleared = write(&app, Method::PATCH, &token, None, json!({"about_me": null})).await;
+    assert_eq!(cleared.status, StatusCode::OK);
+    assert_eq!(cleared.body["about_me"], Value::Null);
+    assert_eq!(cleared.body["display_name"], "Ada");
+    assert_eq!(cleared.body["avatar_url"], "https://img.clerk.com/ada.png");
+
+    let renamed = write(&app, Method::PATCH, &token, None, json!({"display_name": "Ada L."})).await;
+    assert_eq!(renamed.body["display_name"], "Ada L.");
+    assert_eq!(renamed.body["avatar_url"], "https://img.clerk.com/ada.png");
+
+    // Every profile keeps a display name
+    let response = write(&app, Method::PATCH, &token, None, json!({"display_name": null})).await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn put_replaces_every_editable_field() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+    let set = json!({"display_name": "Ada", "about_me": "Hi", "avatar_url": "https://img.clerk.com/ada.png"});
+    assert_eq!(write(&app, Method::PUT, &token, None, set).await.status, StatusCode::OK);
+
+    let replaced = write(&app, Method::PUT, &token, None, json!({"display_name": "Ada L."})).await;
+    assert_eq!(replaced.status, StatusCode::OK);
+    assert_eq!(replaced.body["display_name"], "Ada L.");
+    assert_eq!(replaced.body["about_me"], Value::Null);
+    assert_eq!(replaced.body["avatar_url"], Value::Null);
+
+    let response = write(&app, Method::PUT, &token, None, json!({"about_me": "No name"})).await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    let me = app.get("/api/v1/profiles/me", Some(&token)).await;
+    assert_eq!(me.body["display_name"], "Ada L.");
+    assert_eq!(me.body["about_me"], Value::Null);
+}
+
+#[tokio::test]
+async fn profile_carries_its_version_as_etag() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+
+    let me = app.get("/api/v1/profiles/me", Some(&token)).await;
+    assert_eq!(etag(&me), format!("\"{}\"", me.body["version"]));
+
+    let patched = write(&app, Method::PATCH, &token, None, json!({"about_me": "Hi"})).await;
+    assert_eq!(patched.status, StatusCode::OK);
+    assert_ne!(etag(&patched), etag(&me));
+    assert_eq!(etag(&patched), format!("\"{}\"", patched.body["version"]));
+}
+
+#[tokio::test]
+async fn second_write_with_the_same_etag_fails() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+    let tag = etag(&app.get("/api/v1/profiles/me", Some(&token)).await);
+
+    let first = write(&app, Method::PATCH, &token, Some(&tag), json!({"about_me": "First"})).await;
+    assert_eq!(first.status, StatusCode::OK);
+
+    let second = write(&app, Method::PATCH, &token, Some(&tag), json!({"about_me": "Second"})).await;
+    assert_eq!(second.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(second.body["error"], "precondition_failed");
+
+    let me = app.get("/api/v1/profiles/me", Some(&token)).await;
+    assert_eq!(me.body["about_me"], "First");
+
+    // The fresh tag works, also for PUT and in a list
+    let list = format!("\"0\", {}", etag(&me));
+    let put = write(&app, Method::PUT, &token, Some(&list), json!({"display_name": "Ada"})).await;
+    assert_eq!(put.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn if_match_forms() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+    let me = app.get("/api/v1/profiles/me", Some(&token)).await;
+
+    let weak = format!("W/{}", etag(&me));
+    let response = write(&app, Method::PATCH, &token, Some(&weak), json!({"about_me": "Hi"})).await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+
+    let response = write(&app, Method::PATCH, &token, Some("*"), json!({"about_me": "Hi"})).await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn stale_body_version_fails() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+    let version = app.get("/api/v1/profiles/me", Some(&token)).await.body["version"].clone();
+
+    let first = write(&app, Method::PATCH, &token, None, json!({"about_me": "First", "version": version})).await;
+    assert_eq!(first.status, StatusCode::OK);
+
+    let stale = write(&app, Method::PATCH, &token, None, json!({"about_me": "Second", "version": version})).await;
+    assert_eq!(stale.status, StatusCode::PRECONDITION_FAILED);
+
+    let put = write(
+        &app,
+        Method::PUT,
+        &token,
+        None,
+        json!({"display_name": "Ada", "version": version}),
+    )
+    .await;
+    assert_eq!(put.status, StatusCode::PRECONDITION_FAILED);
+}
+
+/// Profiles that let another writer in between a precondition check and the
+/// next update, once armed
+struct RacingProfiles {
+    inner: InMemoryProfileRepository,
+    armed: AtomicBool,
+}
+
+#[async_trait]
+impl ProfileRepository for RacingProfiles {
+    async fn find_by_user_id(&self, user_id: &str) -> Result<Option<Profile>, DbError> {
+        self.inner.find_by_user_id(user_id).await
+    }
+
+    async fn find_by_clerk_id(&self, clerk_id: &str) -> Result<Option<Profile>, DbError> {
+        self.inner.find_by_clerk_id(clerk_id).await
+    }
+
+    async fn find_by_handle(&self, handle: &str) -> Result<Option<Profile>, DbError> {
+        self.inner.find_by_handle(handle).await
+    }
+
+    async fn create(&self, profile: NewProfile) -> Result<Profile, DbError> {
+        self.inner.create(profile).await
+    }
+
+    async fn update(
+        &self,
+        user_id: &str,
+        update: ProfileUpdate,
+        expected_version: Option<u64>,
+    ) -> Result<Profile, DbError> {
+        if self.armed.swap(false, Ordering::SeqCst) {
+            let other = ProfileUpdate {
+                about_me: Patch::Value("Other writer".to_string()),
+                ..Default::default()
+            };
+            self.inner.update(user_id, other, None).await?;
+        }
+        self.inner.update(user_id, update, expected_version).await
+    }
+
+    async fn delete(&self, user_id: &str) -> Result<(), DbError> {
+        self.inner.delete(user_id).await
+    }
+
+    async fn tombstone(&self, user_id: &str) -> Result<Profile, DbError> {
+        self.inner.tombstone(user_id).await
+    }
+
+    async fn set_handle(&self, user_id: &str, handle: &str) -> Result<Profile, DbError> {
+        self.inner.set_handle(user_id, handle).await
+    }
+
+    async fn set_roles(&self, user_id: &str, roles: &[Role]) -> Result<Profile, DbError> {
+        self.inner.set_roles(user_id, roles).await
+    }
+
+    async fn schedule_deletion(
+        &self,
+        user_id: &str,
+        requested_by: &str,
+        scheduled_for: DateTime<Utc>,
+    ) -> Result<Profile, DbError> {
+        self.inner.schedule_deletion(user_id, requested_by, scheduled_for).await
+    }
+
+    async fn cancel_deletion(&self, user_id: &str) -> Result<Profile, DbError> {
+        self.inner.cancel_deletion(user_id).await
+    }
+
+    async fn due_for_deletion(&self, now: DateTime<Utc>) -> Result<Vec<Profile>, DbError> {
+        self.inner.due_for_deletion(now).await
+    }
+}
+
+#[tokio::test]
+async fn write_racing_past_the_precondition_fails() {
+    let profiles = Arc::new(RacingProfiles {
+        inner: InMemoryProfileRepository::new(),
+        armed: AtomicBool::new(false),
+    });
+    let mut repositories = Repositories::in_memory();
+    repositories.profiles = profiles.clone();
+    let app = TestApp::new(common::unused_db(), repositories);
+    let token = app.token("ada");
+    let tag = etag(&app.get("/api/v1/profiles/me", Some(&token)).await);
+
+    profiles.armed.store(true, Ordering::SeqCst);
+    let response = write(&app, Method::PATCH, &token, Some(&tag), json!({"about_me": "Mine"})).await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+
+    let me = app.get("/api/v1/profiles/me", Some(&token)).await;
+    assert_eq!(me.body["about_me"], "Other writer");
+}
@@ -0,0 +1,162 @@
+//! Provisioning profiles for identities: concurrent first requests,
+//! profiles from before identities were tracked, and the identity a session
+//! signed in with
+
+mod common;
+
+use std::sync::Arc;
+
+use axum::http::{Method, StatusCode};
+use tokio::sync::Barrier;
+
+use cynnycty_backend::repository::memory::{InMemoryAuthIdentityRepository, InMemoryProfileRepository};
+use cynnycty_backend::repository::{
+    find_by_identity, find_or_create_by_identity, ArcadeAuthIdentityRepository, ArcadeProfileRepository,
+    AuthIdentityRepository, NewProfile, ProfileRepository,
+};
+use cynnycty_backend::testing::ArcadeStub;
+
+use common::{migrated, TestApp};
+
+const CALLERS: usize = 16;
+
+/// Run `CALLERS` simultaneous `find_or_create_by_identity` calls for the
+/// same identity and return the userId each one got
+async fn provision_concurrently(
+    profiles: Arc<dyn ProfileRepository>,
+    identities: Arc<dyn AuthIdentityRepository>,
+) -> Vec<String> {
+    let barrier = Arc::new(Barrier::new(CALLERS));
+    let tasks: Vec<_> = (0..CALLERS)
+        .map(|_| {
+            let (profiles, identities, barrier) = (profiles.clone(), identities.clone(), barrier.clone());
+            tokio::spawn(async move {
+                barrier.wait().await;
+                find_or_create_by_identity(profiles.as_ref(), identities.as_ref(), "dev", "ada", || {
+                    "Ada".to_string()
+                })
+                .await
+                .expect("provisioning succeeds")
+                .user_id
+            })
+        })
+        .collect();
+
+    let mut user_ids = Vec::new();
+    for task in tasks {
+        user_ids.push(task.await.unwrap());
+    }
+    user_ids
+}
+
+fn assert_single_user(user_ids: &[String]) -> &str {
+    let first = &user_ids[0];
+    assert!(user_ids.iter().all(|user_id| user_id == first), "{:?}", user_ids);
+    first
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn parallel_first_requests_in_memory() {
+    let profiles = Arc::new(InMemoryProfileRepository::new());
+    let identities = Arc::new(InMemoryAuthIdentityRepository::new());
+
+    let user_ids = provision_concurrently(profiles.clone(), identities.clone()).await;
+    let user_id = assert_single_user(&user_ids);
+
+    let linked = identities.list_for_user(user_id).await.unwrap();
+    assert_eq!(linked.len(), 1);
+    let stored: Vec<_> = profiles.all().into_iter().map(|profile| profile.user_id).collect();
+    assert_eq!(stored, [user_id]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn parallel_first_requests_on_arcade() {
+    let stub = ArcadeStub::start().await;
+    let db = migrated(&stub).await;
+    let profiles = Arc::new(ArcadeProfileRepository::new(db.clone()));
+    let identities = Arc::new(ArcadeAuthIdentityRepository::new(db));
+
+    let user_ids = provision_concurrently(profiles, identities).await;
+    let user_id = assert_single_user(&user_ids);
+
+    let linked = stub.records("cynnycty", "AuthIdentity");
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked[0]["userId"], user_id);
+    let stored = stub.records("cynnycty", "Profile");
+    assert_eq!(stored.len(), 1, "orphaned profiles left behind: {:?}", stored);
+    assert_eq!(stored[0]["userId"], user_id);
+}
+
+/// A profile created before identities were tracked, keyed by Clerk user ID
+async fn legacy_profile(profiles: &dyn ProfileRepository, clerk_id: &str) -> String {
+    profiles
+        .create(NewProfile {
+            user_id: "legacy-user".to_string(),
+            clerk_id: Some(clerk_id.to_string()),
+            display_name: Some("Legacy".to_string()),
+        })
+        .await
+        .unwrap()
+        .user_id
+}
+
+#[tokio::test]
+async fn legacy_profiles_are_linked_for_clerk_only() {
+    let profiles = InMemoryProfileRepository::new();
+    let identities = InMemoryAuthIdentityRepository::new();
+    let legacy = legacy_profile(&profiles, "user_2legacy").await;
+
+    for provider in ["dev", "oidc"] {
+        assert!(
+            find_by_identity(&profiles, &identities, provider, "user_2legacy")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let created = find_or_create_by_identity(&profiles, &identities, provider, "user_2legacy", || {
+            "Other".to_string()
+        })
+        .await
+        .unwrap();
+        assert_ne!(created.user_id, legacy, "{} subject took over the Clerk profile", provider);
+    }
+
+    let linked = find_or_create_by_identity(&profiles, &identities, "clerk", "user_2legacy", || {
+        "Clerk".to_string()
+    })
+    .await
+    .unwrap();
+    assert_eq!(linked.user_id, legacy);
+    assert_eq!(identities.list_for_user(&legacy).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn dev_token_cannot_claim_a_clerk_profile() {
+    let app = TestApp::in_memory();
+    let legacy = legacy_profile(app.state.profiles.as_ref(), "user_2legacy").await;
+
+    let me = app.get("/api/v1/profiles/me", Some(&app.token("user_2legacy"))).await;
+
+    assert_eq!(me.status, axum::http::StatusCode::OK);
+    assert_ne!(me.body["user_id"], legacy.as_str());
+    assert!(app.state.identities.list_for_user(&legacy).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn signed_in_identity_cannot_be_unlinked() {
+    let app = TestApp::in_memory();
+    let token = app.token("ada");
+    app.get("/api/v1/profiles/me", Some(&token)).await;
+
+    let response = app
+        .request(Method::DELETE, "/api/v1/identities/dev/ada", Some(&token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert!(response.body["message"].as_str().unwrap().contains("signed in with"));
+
+    // Same subject under another provider is a different identity
+    let response = app
+        .request(Method::DELETE, "/api/v1/identities/clerk/ada", Some(&token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
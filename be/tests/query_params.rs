@@ -0,0 +1,78 @@
+//! Values reach ArcadeDB as bound parameters, never as SQL text
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use cynnycty_backend::patch::Patch;
+use cynnycty_backend::repository::{ArcadeProfileRepository, NewProfile, ProfileRepository, ProfileUpdate};
+use cynnycty_backend::testing::ArcadeStub;
+
+use common::{migrated, TestApp};
+
+const INJECTION: &str = "O'Brien'; DELETE FROM Profile --";
+
+#[tokio::test]
+async fn quotes_and_sql_fragments_round_trip() {
+    let stub = ArcadeStub::start().await;
+    let profiles = ArcadeProfileRepository::new(migrated(&stub).await);
+
+    for user_id in ["user-1", "user-2"] {
+        profiles
+            .create(NewProfile {
+                user_id: user_id.to_string(),
+                clerk_id: None,
+                display_name: Some("Ada".to_string()),
+            })
+            .await
+            .unwrap();
+    }
+
+    let about_me = r#"say "hi" \ :userId ` UNION SELECT FROM SchemaVersion"#;
+    profiles
+        .update(
+            "user-1",
+            ProfileUpdate {
+                display_name: Patch::Value(INJECTION.to_string()),
+                about_me: Patch::Value(about_me.to_string()),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let stored = profiles.find_by_user_id("user-1").await.unwrap().unwrap();
+    assert_eq!(stored.display_name.as_deref(), Some(INJECTION));
+    assert_eq!(stored.about_me.as_deref(), Some(about_me));
+
+    // The other profile is untouched and still there
+    let other = profiles.find_by_user_id("user-2").await.unwrap().unwrap();
+    assert_eq!(other.display_name.as_deref(), Some("Ada"));
+    assert_eq!(stub.records("cynnycty", "Profile").len(), 2);
+
+    // A lookup by the same text matches nothing rather than everything
+    assert!(profiles.find_by_user_id(INJECTION).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn quotes_round_trip_through_the_api() {
+    let stub = ArcadeStub::start().await;
+    let app = TestApp::arcade(&stub).await;
+    let token = app.token("ada");
+
+    let updated = app
+        .request(
+            Method::PUT,
+            "/api/v1/profiles/me",
+            Some(&token),
+            Some(json!({"display_name": INJECTION})),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK);
+
+    let read = app.get("/api/v1/profiles/me", Some(&token)).await;
+    assert_eq!(read.body["display_name"], INJECTION);
+    assert_eq!(stub.records("cynnycty", "Profile").len(), 1);
+}
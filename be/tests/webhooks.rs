@@ -0,0 +1,190 @@
+//! Clerk user webhooks, signed as Svix would
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::{json, Value};
+
+use cynnycty_backend::erasure::purge_due;
+use cynnycty_backend::repository::{find_by_identity, subject_hash, Profile};
+use cynnycty_backend::webhooks::svix::{SignatureError, SvixVerifier, TIMESTAMP_TOLERANCE};
+
+use common::{TestApp, CLERK_WEBHOOK_SECRET};
+
+/// Some other endpoint's secret, or the previous one during a rotation
+const OTHER_SECRET: &str = "whsec_b3RoZXItc2VjcmV0LW90aGVyLXNlY3JldA==";
+
+fn verifier() -> SvixVerifier {
+    SvixVerifier::new(CLERK_WEBHOOK_SECRET).unwrap()
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// Post `body` with the given Svix headers
+async fn post(app: &TestApp, id: &str, timestamp: &str, signature: &str, body: String) -> StatusCode {
+    let request = Request::post("/api/v1/webhooks/clerk")
+        .header("content-type", "application/json")
+        .header("svix-id", id)
+        .header("svix-timestamp", timestamp)
+        .header("svix-signature", signature)
+        .body(Body::from(body))
+        .unwrap();
+    app.send(request).await.status
+}
+
+/// Post `event`, correctly signed and timestamped now
+async fn deliver(app: &TestApp, id: &str, event: Value) -> StatusCode {
+    let body = event.to_string();
+    let timestamp = now().to_string();
+    let signature = verifier().sign(id, &timestamp, body.as_bytes());
+    post(app, id, &timestamp, &signature, body).await
+}
+
+fn created(clerk_id: &str) -> Value {
+    json!({
+        "type": "user.created",
+        "data": {"id": clerk_id, "first_name": "Ada", "last_name": "Lovelace"},
+    })
+}
+
+async fn profile(app: &TestApp, clerk_id: &str) -> Option<Profile> {
+    find_by_identity(app.state.profiles.as_ref(), app.state.identities.as_ref(), "clerk", clerk_id)
+        .await
+        .unwrap()
+}
+
+async fn stored_name(app: &TestApp, clerk_id: &str) -> Option<String> {
+    profile(app, clerk_id).await.expect("profile for the Clerk user").display_name
+}
+
+#[tokio::test]
+async fn clerk_names_follow_the_display_name_rules() {
+    let app = TestApp::in_memory();
+
+    let created = json!({
+        "type": "user.created",
+        "data": {"id": "user_2abc", "first_name": "\u{202e}Ada\u{0007}", "last_name": "x".repeat(80)},
+    });
+    assert_eq!(deliver(&app, "msg_1", created).await, StatusCode::NO_CONTENT);
+
+    let name = stored_name(&app, "user_2abc").await.unwrap();
+    assert_eq!(name.chars().count(), 50);
+    assert!(name.starts_with("Ada xxx"), "{:?}", name);
+
+    let updated = json!({
+        "type": "user.updated",
+        "data": {"id": "user_2abc", "first_name": "  Ada\tLovelace\n ", "last_name": null},
+    });
+    assert_eq!(deliver(&app, "msg_2", updated).await, StatusCode::NO_CONTENT);
+    assert_eq!(stored_name(&app, "user_2abc").await.as_deref(), Some("Ada Lovelace"));
+
+    // Nothing usable left: the stored name stays
+    let blank = json!({
+        "type": "user.updated",
+        "data": {"id": "user_2abc", "first_name": "\u{202e}", "username": " "},
+    });
+    assert_eq!(deliver(&app, "msg_3", blank).await, StatusCode::NO_CONTENT);
+    assert_eq!(stored_name(&app, "user_2abc").await.as_deref(), Some("Ada Lovelace"));
+}
+
+#[tokio::test]
+async fn bad_signatures_are_rejected() {
+    let app = TestApp::in_memory();
+    let body = created("user_2abc").to_string();
+    let timestamp = now().to_string();
+
+    // Signed for a different body
+    let signature = verifier().sign("msg_1", &timestamp, b"{}");
+    assert_eq!(
+        post(&app, "msg_1", &timestamp, &signature, body.clone()).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    // Signed with another secret
+    let other = SvixVerifier::new(OTHER_SECRET).unwrap();
+    let signature = other.sign("msg_1", &timestamp, body.as_bytes());
+    assert_eq!(
+        post(&app, "msg_1", &timestamp, &signature, body.clone()).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    assert_eq!(
+        post(&app, "msg_1", &timestamp, "v1,bm90LWEtc2lnbmF0dXJl", body).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert!(profile(&app, "user_2abc").await.is_none());
+}
+
+#[tokio::test]
+async fn timestamps_outside_the_tolerance_are_rejected() {
+    let verifier = verifier();
+    let body = b"{}";
+    let sent_at = 1_700_000_000;
+    let timestamp = sent_at.to_string();
+    let signature = verifier.sign("msg_1", &timestamp, body);
+    let tolerance = TIMESTAMP_TOLERANCE.as_secs() as i64;
+
+    for now in [sent_at - tolerance, sent_at, sent_at + tolerance] {
+        assert!(verifier.verify_at("msg_1", &timestamp, &signature, body, now).is_ok(), "at {}", now);
+    }
+    for now in [sent_at - tolerance - 1, sent_at + tolerance + 1] {
+        assert!(matches!(
+            verifier.verify_at("msg_1", &timestamp, &signature, body, now),
+            Err(SignatureError::TimestampOutOfTolerance)
+        ));
+    }
+
+    // A correctly signed replay of an old delivery
+    let app = TestApp::in_memory();
+    let body = created("user_2abc").to_string();
+    let timestamp = (now() - tolerance - 60).to_string();
+    let signature = verifier.sign("msg_1", &timestamp, body.as_bytes());
+    assert_eq!(post(&app, "msg_1", &timestamp, &signature, body).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn any_listed_signature_may_match() {
+    let app = TestApp::in_memory();
+    let body = created("user_2abc").to_string();
+    let timestamp = now().to_string();
+
+    // During a secret rotation Svix signs with both secrets
+    let old = SvixVerifier::new(OTHER_SECRET).unwrap();
+    let signatures = format!(
+        "{} {}",
+        old.sign("msg_1", &timestamp, body.as_bytes()),
+        verifier().sign("msg_1", &timestamp, body.as_bytes())
+    );
+
+    assert_eq!(post(&app, "msg_1", &timestamp, &signatures, body).await, StatusCode::NO_CONTENT);
+    assert!(profile(&app, "user_2abc").await.is_some());
+}
+
+#[tokio::test]
+async fn deleted_users_are_tombstoned_and_erased() {
+    let app = TestApp::in_memory();
+    assert_eq!(deliver(&app, "msg_1", created("user_2abc")).await, StatusCode::NO_CONTENT);
+
+    let deleted = json!({"type": "user.deleted", "data": {"id": "user_2abc", "deleted": true}});
+    assert_eq!(deliver(&app, "msg_2", deleted.clone()).await, StatusCode::NO_CONTENT);
+
+    let tombstoned = profile(&app, "user_2abc").await.unwrap();
+    assert!(tombstoned.is_deleted());
+    assert_eq!(tombstoned.display_name, None);
+    assert_eq!(tombstoned.deletion_requested_by.as_deref(), Some("clerk"));
+    assert!(tombstoned.deletion_scheduled_for.unwrap() <= chrono::Utc::now());
+
+    // Redelivery is a no-op
+    assert_eq!(deliver(&app, "msg_2", deleted).await, StatusCode::NO_CONTENT);
+
+    // Erasure is due right away, and a late update does not bring it back
+    assert_eq!(purge_due(&app.state).await.unwrap(), 1);
+    assert!(profile(&app, "user_2abc").await.is_none());
+    assert!(app.state.erasures.is_erased(&subject_hash("clerk", "user_2abc")).await.unwrap());
+
+    assert_eq!(deliver(&app, "msg_3", created("user_2abc")).await, StatusCode::NO_CONTENT);
+    assert!(profile(&app, "user_2abc").await.is_none());
+}